@@ -19,9 +19,32 @@
 extern "C" {
 #endif
 
-FABRIC_API bool fabric_init();
-FABRIC_API bool fabric_index_data(const char* key, const uint8_t* data, size_t length);
-FABRIC_API bool fabric_search(const char* query, char** result);
+typedef struct FabricHandle FabricHandle;
+typedef struct FabricResultSet FabricResultSet;
+
+typedef enum FabricError {
+    FABRIC_ERROR_NONE = 0,
+    FABRIC_ERROR_INVALID_ARGUMENT = 1,
+    FABRIC_ERROR_FAILED = 2,
+    FABRIC_ERROR_OVERLOADED = 3
+} FabricError;
+
+FABRIC_API FabricHandle* fabric_open(const char* name, const char* config_path);
+FABRIC_API void fabric_close(FabricHandle* handle);
+FABRIC_API bool fabric_index_data(FabricHandle* handle, const char* key, const uint8_t* data, size_t length);
+FABRIC_API bool fabric_remove(FabricHandle* handle, const char* key);
+FABRIC_API bool fabric_update(FabricHandle* handle, const char* key, const uint8_t* data, size_t length, const char* metadata_json);
+FABRIC_API bool fabric_search(FabricHandle* handle, const char* query, char** result);
+FABRIC_API FabricResultSet* fabric_search_ex(FabricHandle* handle, const char* query, size_t limit, size_t offset);
+FABRIC_API FabricResultSet* fabric_autocomplete(FabricHandle* handle, const char* prefix, size_t limit);
+FABRIC_API size_t fabric_result_count(const FabricResultSet* results);
+FABRIC_API const char* fabric_result_get_key(const FabricResultSet* results, size_t index);
+FABRIC_API float fabric_result_get_score(const FabricResultSet* results, size_t index);
+FABRIC_API const char* fabric_result_get_metadata_json(const FabricResultSet* results, size_t index);
+FABRIC_API const size_t* fabric_result_get_positions(const FabricResultSet* results, size_t index, size_t* count);
+FABRIC_API void fabric_result_free(FabricResultSet* results);
+FABRIC_API bool fabric_flush(FabricHandle* handle);
+FABRIC_API FabricError fabric_last_error(void);
 FABRIC_API void fabric_free_string(char* s);
 
 #ifdef __cplusplus
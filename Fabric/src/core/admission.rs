@@ -0,0 +1,65 @@
+use std::time::{Duration, Instant};
+
+use parking_lot::{Condvar, Mutex};
+
+pub struct Semaphore {
+    available: Mutex<usize>,
+    released: Condvar,
+}
+
+pub struct Permit<'a> {
+    semaphore: &'a Semaphore,
+}
+
+impl Semaphore {
+    pub fn new(permits: usize) -> Self {
+        Semaphore {
+            available: Mutex::new(permits.max(1)),
+            released: Condvar::new(),
+        }
+    }
+
+    pub fn try_acquire(&self, timeout: Duration) -> Option<Permit<'_>> {
+        let deadline = Instant::now() + timeout;
+        let mut available = self.available.lock();
+        while *available == 0 {
+            if self.released.wait_until(&mut available, deadline).timed_out() && *available == 0 {
+                return None;
+            }
+        }
+        *available -= 1;
+        Some(Permit { semaphore: self })
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        *self.semaphore.available.lock() += 1;
+        self.semaphore.released.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_permits_and_queue_timeout() {
+        let semaphore = Arc::new(Semaphore::new(2));
+        let first = semaphore.try_acquire(Duration::ZERO).unwrap();
+        let _second = semaphore.try_acquire(Duration::ZERO).unwrap();
+        assert_eq!(*semaphore.available.lock(), 0);
+        assert!(semaphore.try_acquire(Duration::from_millis(20)).is_none());
+
+        let waiter = {
+            let semaphore = semaphore.clone();
+            thread::spawn(move || semaphore.try_acquire(Duration::from_secs(5)).is_some())
+        };
+        thread::sleep(Duration::from_millis(20));
+        drop(first);
+        assert!(waiter.join().unwrap());
+        assert_eq!(*semaphore.available.lock(), 1);
+    }
+}
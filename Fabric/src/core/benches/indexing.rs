@@ -0,0 +1,58 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use fabric::config::Config;
+use fabric::search::{BatchDocument, SearchIndex};
+
+const DOCUMENT_COUNT: usize = 5_000;
+const WORDS: &[&str] = &[
+    "fabric", "search", "index", "segment", "merge", "query", "vector", "storage", "metadata", "shard",
+    "cluster", "token", "analyzer", "ranking", "document", "report", "quarterly", "budget", "meeting", "notes",
+];
+
+fn documents() -> Vec<BatchDocument> {
+    (0..DOCUMENT_COUNT)
+        .map(|i| {
+            let text: Vec<&str> = (0..120).map(|j| WORDS[(i * 7 + j * 13 + j / 3) % WORDS.len()]).collect();
+            (format!("docs/{}.txt", i), text.join(" ").into_bytes(), None)
+        })
+        .collect()
+}
+
+fn bench_indexing(c: &mut Criterion) {
+    let documents = documents();
+    let performance = Config::default().performance;
+
+    let mut group = c.benchmark_group("indexing");
+    group.throughput(Throughput::Elements(DOCUMENT_COUNT as u64));
+    group.sample_size(10);
+
+    group.bench_function("index_data", |b| {
+        b.iter_batched(
+            || documents.clone(),
+            |documents| {
+                let mut index = SearchIndex::new();
+                for (key, data, metadata) in documents {
+                    index.index_data(&key, &data, metadata);
+                }
+                index
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function("index_batch", |b| {
+        b.iter_batched(
+            || documents.clone(),
+            |documents| {
+                let mut index = SearchIndex::new();
+                index.index_batch(documents, &performance);
+                index
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_indexing);
+criterion_main!(benches);
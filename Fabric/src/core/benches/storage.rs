@@ -0,0 +1,66 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use fabric::persistence::Storage;
+use tempfile::TempDir;
+
+const DOCUMENT_COUNT: usize = 2_000;
+const METADATA: &str = r#"{"source":"bench"}"#;
+
+fn documents() -> Vec<(String, Vec<u8>)> {
+    (0..DOCUMENT_COUNT)
+        .map(|i| {
+            let body = format!("document {} about bulk import throughput and storage batching", i);
+            (format!("docs/{}.txt", i), body.into_bytes())
+        })
+        .collect()
+}
+
+fn open_storage() -> (TempDir, Storage) {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Storage::new(dir.path().join("bench.db")).unwrap();
+    (dir, storage)
+}
+
+fn bench_bulk_import(c: &mut Criterion) {
+    let documents = documents();
+
+    let mut group = c.benchmark_group("bulk_import");
+    group.throughput(Throughput::Elements(DOCUMENT_COUNT as u64));
+    group.sample_size(10);
+
+    group.bench_function("store_document", |b| {
+        b.iter_batched(
+            open_storage,
+            |(dir, storage)| {
+                for (id, data) in &documents {
+                    storage.store_document(id, data, METADATA).unwrap();
+                }
+                (dir, storage)
+            },
+            BatchSize::PerIteration,
+        )
+    });
+
+    group.bench_function("store_batch", |b| {
+        b.iter_batched(
+            open_storage,
+            |(dir, storage)| {
+                let rows = documents.iter().map(|(id, data)| (id.as_str(), data.as_slice(), METADATA));
+                storage.store_batch(rows).unwrap();
+                (dir, storage)
+            },
+            BatchSize::PerIteration,
+        )
+    });
+
+    group.bench_function("scan_documents", |b| {
+        let (_dir, storage) = open_storage();
+        let rows = documents.iter().map(|(id, data)| (id.as_str(), data.as_slice(), METADATA));
+        storage.store_batch(rows).unwrap();
+        b.iter(|| storage.scan_documents(256).filter_map(Result::ok).count())
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_bulk_import);
+criterion_main!(benches);
@@ -23,6 +23,77 @@ pub struct SearchConfig {
     pub enable_fuzzy: bool,
     pub enable_vector: bool,
     pub min_score: f32,
+    #[serde(default)]
+    pub matcher: MatcherKind,
+    #[serde(default = "default_max_edit_distance")]
+    pub max_edit_distance: usize,
+    #[serde(default)]
+    pub analyzer: AnalyzerConfig,
+    #[serde(default)]
+    pub field_analyzers: HashMap<String, AnalyzerConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum MatcherKind {
+    #[default]
+    Subsequence,
+    Typo,
+}
+
+fn default_max_edit_distance() -> usize {
+    2
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct AnalyzerConfig {
+    pub tokenizer: TokenizerKind,
+    pub lowercase: bool,
+    pub ascii_folding: bool,
+    pub stemmer: Option<Language>,
+    pub stop_words: Option<Language>,
+    pub ngram: Option<NGramConfig>,
+}
+
+impl Default for AnalyzerConfig {
+    fn default() -> Self {
+        AnalyzerConfig {
+            tokenizer: TokenizerKind::Word,
+            lowercase: true,
+            ascii_folding: false,
+            stemmer: None,
+            stop_words: None,
+            ngram: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenizerKind {
+    #[default]
+    Word,
+    Whitespace,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[serde(alias = "en")]
+    English,
+    #[serde(alias = "de")]
+    German,
+    #[serde(alias = "fr")]
+    French,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct NGramConfig {
+    pub min_gram: usize,
+    pub max_gram: usize,
+    #[serde(default)]
+    pub edge: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -30,6 +101,10 @@ pub struct StorageConfig {
     pub path: String,
     pub cache_size_mb: usize,
     pub persist_interval_secs: u64,
+    #[serde(default)]
+    pub write_through: bool,
+    #[serde(default)]
+    pub metadata_indexes: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -37,6 +112,12 @@ pub struct PerformanceConfig {
     pub worker_threads: usize,
     pub max_concurrent_searches: usize,
     pub batch_size: usize,
+    #[serde(default = "default_search_queue_timeout_ms")]
+    pub search_queue_timeout_ms: u64,
+}
+
+fn default_search_queue_timeout_ms() -> u64 {
+    250
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -54,16 +135,23 @@ impl Default for Config {
                 enable_fuzzy: true,
                 enable_vector: true,
                 min_score: 0.1,
+                matcher: MatcherKind::Subsequence,
+                max_edit_distance: default_max_edit_distance(),
+                analyzer: AnalyzerConfig::default(),
+                field_analyzers: HashMap::new(),
             },
             storage: StorageConfig {
                 path: "./data".to_string(),
                 cache_size_mb: 1024,
                 persist_interval_secs: 60,
+                write_through: false,
+                metadata_indexes: Vec::new(),
             },
             performance: PerformanceConfig {
                 worker_threads: num_cpus::get(),
                 max_concurrent_searches: 100,
                 batch_size: 1000,
+                search_queue_timeout_ms: default_search_queue_timeout_ms(),
             },
             features: FeaturesConfig {
                 enable_metrics: true,
@@ -80,7 +168,7 @@ pub fn init_config(path: Option<&str>) -> Result<(), String> {
         None => Config::default(),
     };
     
-    if let Some(mut config_lock) = CONFIG.write().ok() {
+    if let Ok(mut config_lock) = CONFIG.write() {
         *config_lock = Some(config);
         Ok(())
     } else {
@@ -96,7 +184,7 @@ pub fn get_config() -> Option<Config> {
     }
 }
 
-fn load_config(path: &str) -> Result<Config, String> {
+pub fn load_config(path: &str) -> Result<Config, String> {
     let config_str = fs::read_to_string(path)
         .map_err(|e| format!("Failed to read config file: {}", e))?;
     
@@ -133,6 +221,22 @@ mod tests {
         let _: Config = toml::from_str(&config_str).unwrap();
     }
     
+    #[test]
+    fn test_field_analyzer_serialization() {
+        let mut config = Config::default();
+        config.search.field_analyzers.insert(
+            "title".to_string(),
+            AnalyzerConfig {
+                stemmer: Some(Language::English),
+                ngram: Some(NGramConfig { min_gram: 2, max_gram: 3, edge: true }),
+                ..AnalyzerConfig::default()
+            },
+        );
+        let config_str = toml::to_string_pretty(&config).unwrap();
+        let loaded: Config = toml::from_str(&config_str).unwrap();
+        assert_eq!(loaded.search.field_analyzers, config.search.field_analyzers);
+    }
+    
     #[test]
     fn test_config_file_io() {
         let temp_dir = tempdir().unwrap();
@@ -1,11 +1,7 @@
-use crate::distributed::Message;
 use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use uuid::Uuid;
-
-const SHARDS_PER_NODE: usize = 4;
 
 #[derive(Debug)]
 pub struct Coordinator {
@@ -26,33 +22,36 @@ impl Coordinator {
     }
 
     pub async fn add_node(&self, node_id: String, addr: SocketAddr) -> Result<(), String> {
-        let mut nodes = self.nodes.write().await;
-        let mut node_shards = self.node_shards.write().await;
-        let mut shard_map = self.shard_map.write().await;
-        
-        if nodes.contains_key(&node_id) {
-            return Err("Node already exists".to_string());
+        {
+            let mut nodes = self.nodes.write().await;
+            let mut node_shards = self.node_shards.write().await;
+            
+            if nodes.contains_key(&node_id) {
+                return Err("Node already exists".to_string());
+            }
+            
+            nodes.insert(node_id.clone(), addr);
+            node_shards.insert(node_id.clone(), HashSet::new());
         }
         
-        nodes.insert(node_id.clone(), addr);
-        node_shards.insert(node_id.clone(), HashSet::new());
-        
         self.rebalance_shards().await;
         
         Ok(())
     }
     
     pub async fn remove_node(&self, node_id: &str) -> Result<(), String> {
-        let mut nodes = self.nodes.write().await;
-        let mut node_shards = self.node_shards.write().await;
-        
-        if !nodes.contains_key(node_id) {
-            return Err("Node not found".to_string());
+        {
+            let mut nodes = self.nodes.write().await;
+            let mut node_shards = self.node_shards.write().await;
+            
+            if !nodes.contains_key(node_id) {
+                return Err("Node not found".to_string());
+            }
+            
+            nodes.remove(node_id);
+            node_shards.remove(node_id);
         }
         
-        nodes.remove(node_id);
-        node_shards.remove(node_id);
-        
         self.rebalance_shards().await;
         
         Ok(())
@@ -68,23 +67,18 @@ impl Coordinator {
         }
         
         let total_shards = 1024;
-        let shards_per_node = total_shards / nodes.len().max(1);
-        
-        let mut shard = 0;
-        let mut node_index = 0;
         let node_ids: Vec<String> = nodes.keys().cloned().collect();
         
-        for _ in 0..total_shards {
-            let node_id = &node_ids[node_index % node_ids.len()];
-            
+        for shards in node_shards.values_mut() {
+            shards.clear();
+        }
+        
+        for (shard, node_id) in (0..total_shards).zip(node_ids.iter().cycle()) {
             shard_map.insert(shard, node_id.clone());
             node_shards
                 .get_mut(node_id)
                 .unwrap()
                 .insert(shard);
-            
-            shard += 1;
-            node_index += 1;
         }
     }
     
@@ -101,6 +95,7 @@ impl Coordinator {
 mod tests {
     use super::*;
     use std::net::{IpAddr, Ipv4Addr};
+    use uuid::Uuid;
     
     #[tokio::test]
     async fn test_add_remove_node() {
@@ -5,12 +5,11 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
+pub mod node;
 mod coordinator;
-mod node;
 mod sharding;
 
 pub use coordinator::*;
-pub use node::*;
 pub use sharding::*;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -54,7 +53,7 @@ impl Cluster {
             shards: Vec::new(),
         };
         
-        self.nodes.write().await.insert(node_id, node);
+        self.nodes.write().await.insert(node_id.clone(), node);
         self.coordinator.add_node(node_id, node_addr).await?;
         
         Ok(())
@@ -68,9 +67,8 @@ impl Cluster {
     
     pub async fn get_node_for_key(&self, key: &str) -> Option<Node> {
         let shard = self.shard_key(key);
-        let shard_map = self.shard_map.read().await;
-        shard_map.get(&shard)
-            .and_then(|node_id| self.nodes.read().blocking_get().get(node_id).cloned())
+        let node_id = self.shard_map.read().await.get(&shard).cloned()?;
+        self.nodes.read().await.get(&node_id).cloned()
     }
     
     fn shard_key(&self, key: &str) -> u64 {
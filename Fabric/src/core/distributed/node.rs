@@ -1,11 +1,13 @@
 use crate::distributed::Message;
-use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use futures_util::{SinkExt, StreamExt};
 use tokio::net::TcpStream;
 use tokio::sync::RwLock;
-use tokio_tungstenite::{connect_async, tungstenite::Message as WsMessage, WebSocketStream};
+use tokio_tungstenite::{
+    connect_async, tungstenite::Message as WsMessage, MaybeTlsStream, WebSocketStream,
+};
 use uuid::Uuid;
 
 #[derive(Debug)]
@@ -13,7 +15,7 @@ pub struct Node {
     pub id: String,
     pub addr: SocketAddr,
     pub shards: Vec<u64>,
-    connections: Arc<RwLock<HashMap<String, WebSocketStream<TcpStream>>>>,
+    connections: Arc<RwLock<HashMap<String, WebSocketStream<MaybeTlsStream<TcpStream>>>>>,
 }
 
 impl Node {
@@ -39,13 +41,13 @@ impl Node {
     }
 
     pub async fn send_message(&self, addr: &SocketAddr, message: Message) -> Result<(), String> {
-        let connections = self.connections.read().await;
-        if let Some(stream) = connections.get(&addr.to_string()) {
+        let mut connections = self.connections.write().await;
+        if let Some(stream) = connections.get_mut(&addr.to_string()) {
             let msg = serde_json::to_string(&message)
                 .map_err(|e| format!("Failed to serialize message: {}", e))?;
             
             stream
-                .write_message(WsMessage::Text(msg))
+                .send(WsMessage::Text(msg))
                 .await
                 .map_err(|e| format!("Failed to send message: {}", e))?;
             
@@ -55,7 +57,7 @@ impl Node {
         }
     }
 
-    pub async fn join_cluster(&self, coordinator_addr: SocketAddr) -> Result<(), String> {
+    pub async fn join_cluster(&mut self, coordinator_addr: SocketAddr) -> Result<(), String> {
         let url = format!("ws://{}/ws", coordinator_addr);
         let (mut ws_stream, _) = connect_async(url)
             .await
@@ -70,7 +72,7 @@ impl Node {
             .map_err(|e| format!("Failed to serialize join message: {}", e))?;
 
         ws_stream
-            .write_message(WsMessage::Text(msg))
+            .send(WsMessage::Text(msg))
             .await
             .map_err(|e| format!("Failed to send join message: {}", e))?;
 
@@ -111,11 +113,11 @@ impl Node {
         }
     }
 
-    async fn store_local(&self, key: &str, value: Vec<u8>) -> Result<(), String> {
+    async fn store_local(&self, _key: &str, _value: Vec<u8>) -> Result<(), String> {
         Ok(())
     }
 
-    async fn forward_data(&self, shard: u64, key: &str, value: Vec<u8>) -> Result<(), String> {
+    async fn forward_data(&self, _shard: u64, _key: &str, _value: Vec<u8>) -> Result<(), String> {
         Ok(())
     }
 
@@ -35,7 +35,7 @@ impl ConsistentHash {
             (hash, node_id.to_string())
         }).collect::<Vec<_>>();
         
-        for (hash, node_id_str) in hashes {
+        for (hash, node_id_str) in hashes.iter().cloned() {
             ring.insert(hash, node_id_str);
         }
         
@@ -66,7 +66,7 @@ impl ConsistentHash {
         }
         
         let hash = Self::hash(key);
-        let range = ring.range(hash..);
+        let mut range = ring.range(hash..);
         
         if let Some((_, node_id)) = range.next() {
             return Some(node_id.clone());
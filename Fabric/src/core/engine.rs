@@ -0,0 +1,839 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::Duration;
+
+use crate::admission::{Permit, Semaphore};
+use crate::flush::Flusher;
+
+use crate::config::Config;
+use crate::extract::ExtractorRegistry;
+use crate::persistence::{self, CacheStats, CachedStorage, Storage};
+use crate::search::{
+    parse_query, BatchDocument, MergeStatus, SearchIndex, SearchResult, SegmentMerger, SegmentStore, StoredDocument,
+    Suggestion,
+};
+
+const DATABASE_FILE: &str = "fabric.db";
+const INDEX_DIRECTORY: &str = "index";
+const MAX_SEGMENTS: usize = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchError {
+    Overloaded,
+    Failed(String),
+}
+
+impl From<String> for SearchError {
+    fn from(message: String) -> Self {
+        SearchError::Failed(message)
+    }
+}
+
+impl fmt::Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchError::Overloaded => write!(f, "Too many concurrent searches"),
+            SearchError::Failed(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadProgress {
+    pub loaded: usize,
+    pub total: usize,
+}
+
+pub struct Engine {
+    config: Config,
+    index: Arc<RwLock<SearchIndex>>,
+    storage: Option<Arc<CachedStorage>>,
+    segments: Option<Arc<SegmentStore>>,
+    merger: Option<Arc<SegmentMerger>>,
+    dirty: Arc<Mutex<HashSet<String>>>,
+    committing: Arc<Mutex<()>>,
+    flusher: Option<Flusher>,
+    searches: Semaphore,
+}
+
+impl Engine {
+    pub fn new(config: Config) -> Self {
+        Engine {
+            index: Arc::new(RwLock::new(SearchIndex::with_config(config.search.clone()))),
+            searches: Semaphore::new(config.performance.max_concurrent_searches),
+            config,
+            storage: None,
+            segments: None,
+            merger: None,
+            dirty: Arc::new(Mutex::new(HashSet::new())),
+            committing: Arc::new(Mutex::new(())),
+            flusher: None,
+        }
+    }
+
+    pub fn open(config: Config) -> Result<Self, String> {
+        Self::open_with_progress(config, |_| {})
+    }
+
+    pub fn open_with_progress<F>(config: Config, progress: F) -> Result<Self, String>
+    where
+        F: FnMut(LoadProgress),
+    {
+        fs::create_dir_all(&config.storage.path)
+            .map_err(|e| format!("Failed to create storage directory: {}", e))?;
+
+        let db_path = Path::new(&config.storage.path).join(DATABASE_FILE);
+        let storage = Storage::new(&db_path)
+            .map_err(|e| format!("Failed to open storage: {}", e))?;
+        storage
+            .ensure_metadata_indexes(&config.storage.metadata_indexes)
+            .map_err(|e| format!("Failed to create metadata indexes: {}", e))?;
+        let cache_bytes = config.storage.cache_size_mb.saturating_mul(1024 * 1024);
+        let storage = Arc::new(CachedStorage::new(storage, cache_bytes));
+
+        let segments = Arc::new(SegmentStore::open(Path::new(&config.storage.path).join(INDEX_DIRECTORY))?);
+
+        let mut engine = Engine::new(config);
+        engine.load_segments(&segments)?;
+        engine.storage = Some(storage);
+        engine.segments = Some(segments.clone());
+        engine.load_storage(progress)?;
+        let merger = Arc::new(SegmentMerger::spawn(segments.clone(), MAX_SEGMENTS));
+        engine.merger = Some(merger.clone());
+        if engine.config.storage.persist_interval_secs > 0 {
+            let interval = Duration::from_secs(engine.config.storage.persist_interval_secs);
+            let (index, dirty, segments) = (engine.index.clone(), engine.dirty.clone(), segments.clone());
+            let committing = engine.committing.clone();
+            let (storage, write_through) = (engine.storage.clone(), engine.config.storage.write_through);
+            engine.flusher = Some(Flusher::spawn(interval, move || {
+                let committed =
+                    commit_dirty(&index, &dirty, &committing, &segments, storage.as_deref(), write_through);
+                if committed.is_ok_and(|committed| committed > 0) {
+                    merger.notify();
+                }
+            }));
+        }
+        Ok(engine)
+    }
+
+    fn load_segments(&self, segments: &SegmentStore) -> Result<(), String> {
+        let documents = segments.documents()?;
+        self.index_mut()?.restore_batch(documents)?;
+        Ok(())
+    }
+
+    pub fn load_storage<F>(&self, mut progress: F) -> Result<usize, String>
+    where
+        F: FnMut(LoadProgress),
+    {
+        let Some(storage) = &self.storage else {
+            return Ok(0);
+        };
+        let storage = storage.storage();
+        let total = storage
+            .document_count()
+            .map_err(|e| format!("Failed to count stored documents: {}", e))?;
+        progress(LoadProgress { loaded: 0, total });
+
+        let batch_size = self.config.performance.batch_size.max(1);
+        let track = self.segments.is_some();
+        let mut unseen: HashSet<String> = self.index()?.keys().map(str::to_string).collect();
+        let mut batch: Vec<persistence::StoredDocument> = Vec::with_capacity(batch_size.min(total));
+        let (mut scanned, mut loaded) = (0, 0);
+        let mut flush = |batch: &mut Vec<persistence::StoredDocument>| -> Result<(), String> {
+            scanned += batch.len();
+            let mut index = self.index_mut()?;
+            let mut dirty = lock_dirty(&self.dirty)?;
+            let mut documents: Vec<BatchDocument> = Vec::new();
+            for document in batch.drain(..) {
+                unseen.remove(&document.id);
+                if dirty.contains(&document.id) {
+                    continue;
+                }
+                let metadata = parse_metadata(&document.id, &document.metadata)?;
+                let current = index.checksum(&document.id) == Some(crc32fast::hash(&document.data))
+                    && metadata.iter().flatten().all(|(name, value)| {
+                        index.get_metadata(&document.id).and_then(|fields| fields.get(name)) == Some(value)
+                    });
+                if !current {
+                    documents.push((document.id, document.data, metadata));
+                }
+            }
+            if track {
+                dirty.extend(documents.iter().map(|(key, _, _)| key.clone()));
+            }
+            loaded += index.index_batch(documents, &self.config.performance);
+            drop(dirty);
+            drop(index);
+            progress(LoadProgress { loaded: scanned, total });
+            Ok(())
+        };
+
+        for document in storage.scan_documents(batch_size) {
+            batch.push(document.map_err(|e| format!("Failed to read stored documents: {}", e))?);
+            if batch.len() >= batch_size {
+                flush(&mut batch)?;
+            }
+        }
+        if !batch.is_empty() {
+            flush(&mut batch)?;
+        }
+
+        let mut index = self.index_mut()?;
+        let mut dirty = lock_dirty(&self.dirty)?;
+        for key in unseen {
+            if !dirty.contains(&key) && index.remove(&key) && track {
+                dirty.insert(key);
+            }
+        }
+        Ok(loaded)
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn storage(&self) -> Option<&Storage> {
+        self.storage.as_deref().map(CachedStorage::storage)
+    }
+
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.storage.as_ref().map(|storage| storage.stats())
+    }
+
+    pub fn segments(&self) -> Option<&SegmentStore> {
+        self.segments.as_deref()
+    }
+
+    pub fn merge_status(&self) -> Option<MergeStatus> {
+        self.merger.as_ref().map(|merger| merger.status())
+    }
+
+    pub fn index(&self) -> Result<RwLockReadGuard<'_, SearchIndex>, String> {
+        self.index
+            .read()
+            .map_err(|_| "Failed to acquire read lock for index".to_string())
+    }
+
+    pub fn index_mut(&self) -> Result<RwLockWriteGuard<'_, SearchIndex>, String> {
+        self.index
+            .write()
+            .map_err(|_| "Failed to acquire write lock for index".to_string())
+    }
+
+    fn mark_dirty<I: IntoIterator<Item = String>>(&self, keys: I) -> Result<(), String> {
+        if self.segments.is_some() {
+            lock_dirty(&self.dirty)?.extend(keys);
+        }
+        Ok(())
+    }
+
+    fn write_through(&self) -> Option<&CachedStorage> {
+        self.storage.as_deref().filter(|_| self.config.storage.write_through)
+    }
+
+    fn persist(&self, index: &mut SearchIndex, key: &str, data: Option<&[u8]>) -> Result<(), String> {
+        let Some(storage) = self.write_through() else {
+            return Ok(());
+        };
+        let document = index.stored_document(key);
+        let result = if document.deleted {
+            storage.delete_document(key).map(|_| ())
+        } else {
+            let metadata = serde_json::to_string(&document.metadata)
+                .map_err(|e| format!("Failed to serialize metadata: {}", e))?;
+            match data {
+                Some(data) => storage.store_document(key, data, &metadata),
+                None => storage.update_metadata(key, &metadata).map(|_| ()),
+            }
+        };
+        result.map_err(|e| format!("Failed to persist document {}: {}", key, e))?;
+        index.release_payload(key);
+        Ok(())
+    }
+
+    pub fn document(&self, key: &str) -> Result<Option<Arc<Vec<u8>>>, String> {
+        {
+            let index = self.index()?;
+            if !index.contains(key) {
+                return Ok(None);
+            }
+            if let Some(data) = index.payload(key) {
+                return Ok(Some(Arc::new(data.to_vec())));
+            }
+        }
+        let Some(storage) = &self.storage else {
+            return Ok(None);
+        };
+        let (data, hit) = storage
+            .get(key)
+            .map_err(|e| format!("Failed to load document {}: {}", key, e))?;
+        self.record_cache_accesses([hit])?;
+        Ok(data)
+    }
+
+    fn record_cache_accesses<I: IntoIterator<Item = bool>>(&self, hits: I) -> Result<(), String> {
+        if let Some(metrics) = self.index()?.metrics() {
+            for hit in hits {
+                metrics.record_cache_access(hit);
+            }
+        }
+        Ok(())
+    }
+
+    fn phrase_texts(&self, query: &str) -> Result<HashMap<String, String>, String> {
+        let (Some(storage), Ok(parsed)) = (&self.storage, parse_query(query)) else {
+            return Ok(HashMap::new());
+        };
+        let (keys, extractors) = {
+            let index = self.index()?;
+            let keys = index.phrase_candidates(&parsed);
+            if keys.is_empty() {
+                return Ok(HashMap::new());
+            }
+            (keys, index.extractors().clone())
+        };
+
+        let mut texts = HashMap::with_capacity(keys.len());
+        let mut hits = Vec::with_capacity(keys.len());
+        for key in keys {
+            let (data, hit) = storage
+                .get(&key)
+                .map_err(|e| format!("Failed to load document {}: {}", key, e))?;
+            hits.push(hit);
+            if let Some(text) = data.and_then(|data| extractors.extract_text(&key, &data)) {
+                texts.insert(key, text);
+            }
+        }
+        self.record_cache_accesses(hits)?;
+        Ok(texts)
+    }
+
+    pub fn index_data(
+        &self,
+        key: &str,
+        data: &[u8],
+        metadata: Option<HashMap<String, String>>,
+    ) -> Result<bool, String> {
+        let mut index = self.index_mut()?;
+        let indexed = index.index_data(key, data, metadata);
+        self.mark_dirty([key.to_string()])?;
+        self.persist(&mut index, key, Some(data))?;
+        Ok(indexed)
+    }
+
+    pub fn index_batch<I>(&self, documents: I) -> Result<usize, String>
+    where
+        I: IntoIterator<Item = BatchDocument>,
+    {
+        let write_through = self.write_through().is_some();
+        let mut keys = Vec::new();
+        let mut bodies = Vec::new();
+        let documents = documents.into_iter().inspect(|(key, data, _)| {
+            keys.push(key.clone());
+            if write_through {
+                bodies.push(data.clone());
+            }
+        });
+        let builder = self.index()?.batch_builder();
+        let prepared = builder.prepare(documents, &self.config.performance);
+        let mut index = self.index_mut()?;
+        let indexed = index.apply_batch(prepared);
+        if let Some(storage) = self.write_through() {
+            let metadata = keys
+                .iter()
+                .map(|key| {
+                    serde_json::to_string(&index.get_metadata(key).cloned().unwrap_or_default())
+                        .map_err(|e| format!("Failed to serialize metadata: {}", e))
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            let rows = keys
+                .iter()
+                .zip(&bodies)
+                .zip(&metadata)
+                .map(|((key, data), metadata)| (key.as_str(), data.as_slice(), metadata.as_str()));
+            storage
+                .store_batch(rows)
+                .map_err(|e| format!("Failed to persist batch: {}", e))?;
+            for key in &keys {
+                index.release_payload(key);
+            }
+        }
+        self.mark_dirty(keys)?;
+        Ok(indexed)
+    }
+
+    pub fn update(
+        &self,
+        key: &str,
+        data: Option<&[u8]>,
+        metadata: Option<HashMap<String, String>>,
+    ) -> Result<bool, String> {
+        let mut index = self.index_mut()?;
+        let updated = index.update(key, data, metadata);
+        if updated {
+            self.mark_dirty([key.to_string()])?;
+            self.persist(&mut index, key, data)?;
+        }
+        Ok(updated)
+    }
+
+    pub fn remove(&self, key: &str) -> Result<bool, String> {
+        let mut index = self.index_mut()?;
+        let removed = index.remove(key);
+        if removed {
+            self.mark_dirty([key.to_string()])?;
+            self.persist(&mut index, key, None)?;
+        }
+        Ok(removed)
+    }
+
+    pub fn commit(&self) -> Result<usize, String> {
+        let Some(segments) = &self.segments else {
+            return Err("Engine has no persistent storage to commit to".to_string());
+        };
+        let committed = commit_dirty(
+            &self.index,
+            &self.dirty,
+            &self.committing,
+            segments,
+            self.storage.as_deref(),
+            self.config.storage.write_through,
+        )?;
+        if let (true, Some(merger)) = (committed > 0, &self.merger) {
+            merger.notify();
+        }
+        Ok(committed)
+    }
+
+    pub fn search(&self, query: &str, limit: usize, offset: usize) -> Result<Vec<SearchResult>, SearchError> {
+        let _permit = self.admit()?;
+        let texts = self.phrase_texts(query)?;
+        let results = self.index()?.query_with_texts(query, offset.saturating_add(limit), &texts)?;
+        Ok(results.into_iter().skip(offset).collect())
+    }
+
+    pub fn autocomplete(&self, prefix: &str, limit: usize) -> Result<Vec<Suggestion>, SearchError> {
+        let _permit = self.admit()?;
+        Ok(self.index()?.autocomplete(prefix, limit))
+    }
+
+    fn admit(&self) -> Result<Permit<'_>, SearchError> {
+        let timeout = Duration::from_millis(self.config.performance.search_queue_timeout_ms);
+        self.searches.try_acquire(timeout).ok_or(SearchError::Overloaded)
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        self.flusher.take();
+        let _ = self.commit();
+    }
+}
+
+fn lock_dirty(dirty: &Mutex<HashSet<String>>) -> Result<MutexGuard<'_, HashSet<String>>, String> {
+    dirty
+        .lock()
+        .map_err(|_| "Failed to acquire dirty key lock".to_string())
+}
+
+fn parse_metadata(key: &str, metadata: &str) -> Result<Option<HashMap<String, String>>, String> {
+    let value = serde_json::from_str::<serde_json::Value>(metadata)
+        .map_err(|e| format!("Invalid metadata for document {}: {}", key, e))?;
+    match value {
+        serde_json::Value::Null => Ok(None),
+        serde_json::Value::Object(fields) => Ok(Some(
+            fields
+                .into_iter()
+                .map(|(name, value)| match value {
+                    serde_json::Value::String(value) => (name, value),
+                    value => (name, value.to_string()),
+                })
+                .collect(),
+        )),
+        value => Err(format!("Metadata for document {} is not an object: {}", key, value)),
+    }
+}
+
+fn commit_dirty(
+    index: &RwLock<SearchIndex>,
+    dirty: &Mutex<HashSet<String>>,
+    committing: &Mutex<()>,
+    segments: &SegmentStore,
+    storage: Option<&CachedStorage>,
+    write_through: bool,
+) -> Result<usize, String> {
+    let _committing = committing
+        .lock()
+        .map_err(|_| "Failed to acquire commit lock".to_string())?;
+    let keys: Vec<String> = lock_dirty(dirty)?.drain().collect();
+    if keys.is_empty() {
+        return Ok(0);
+    }
+
+    let (mut snapshot, extractors) = match index.read() {
+        Ok(index) => {
+            let snapshot: Vec<(StoredDocument, Option<Arc<Vec<u8>>>)> = keys
+                .iter()
+                .map(|key| (index.stored_document(key), index.payload(key).map(|data| Arc::new(data.to_vec()))))
+                .collect();
+            (snapshot, index.extractors().clone())
+        }
+        Err(_) => {
+            lock_dirty(dirty)?.extend(keys);
+            return Err("Failed to acquire read lock for index".to_string());
+        }
+    };
+
+    let result = match storage {
+        Some(storage) => load_released_texts(storage, &extractors, &mut snapshot),
+        None => Ok(()),
+    };
+    let result = result.and_then(|_| match storage {
+        Some(storage) if !write_through => store_payloads(storage, &snapshot),
+        _ => Ok(()),
+    });
+    let result = result.and_then(|_| segments.commit(snapshot.into_iter().map(|(document, _)| document).collect()));
+    if let Err(e) = result {
+        lock_dirty(dirty)?.extend(keys);
+        return Err(e);
+    }
+
+    if storage.is_some() {
+        let mut index = index
+            .write()
+            .map_err(|_| "Failed to acquire write lock for index".to_string())?;
+        let dirty = lock_dirty(dirty)?;
+        for key in keys.iter().filter(|key| !dirty.contains(*key)) {
+            index.release_document(key);
+        }
+    }
+    Ok(keys.len())
+}
+
+fn load_released_texts(
+    storage: &CachedStorage,
+    extractors: &ExtractorRegistry,
+    snapshot: &mut [(StoredDocument, Option<Arc<Vec<u8>>>)],
+) -> Result<(), String> {
+    for (document, payload) in snapshot.iter_mut() {
+        if document.deleted || document.text.is_some() || payload.is_some() {
+            continue;
+        }
+        let (data, _) = storage
+            .get(&document.key)
+            .map_err(|e| format!("Failed to load document {}: {}", document.key, e))?;
+        document.text = data.and_then(|data| extractors.extract_text(&document.key, &data));
+    }
+    Ok(())
+}
+
+fn store_payloads(
+    storage: &CachedStorage,
+    snapshot: &[(StoredDocument, Option<Arc<Vec<u8>>>)],
+) -> Result<(), String> {
+    let mut rows = Vec::with_capacity(snapshot.len());
+    for (document, payload) in snapshot {
+        if document.deleted {
+            storage
+                .delete_document(&document.key)
+                .map_err(|e| format!("Failed to persist document {}: {}", document.key, e))?;
+            continue;
+        }
+        let metadata = serde_json::to_string(&document.metadata)
+            .map_err(|e| format!("Failed to serialize metadata: {}", e))?;
+        match payload {
+            Some(data) => rows.push((document.key.as_str(), data, metadata)),
+            None => {
+                storage
+                    .update_metadata(&document.key, &metadata)
+                    .map_err(|e| format!("Failed to persist document {}: {}", document.key, e))?;
+            }
+        }
+    }
+    storage
+        .store_batch(rows.iter().map(|(key, data, metadata)| (*key, data.as_slice(), metadata.as_str())))
+        .map_err(|e| format!("Failed to persist documents: {}", e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_engine_lifecycle() {
+        let temp_dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage.path = temp_dir.path().join("data").to_string_lossy().to_string();
+
+        let engine = Engine::open(config).unwrap();
+        assert!(engine.storage().is_some());
+
+        engine.index_data("notes.txt", b"meeting notes", None).unwrap();
+        engine.index_data("todo.txt", b"buy milk", None).unwrap();
+
+        let results = engine.search("notes", 10, 0).unwrap();
+        assert_eq!(results[0].key, "notes.txt");
+        assert!(engine.search("notes", 10, 5).unwrap().is_empty());
+
+        assert!(engine.remove("notes.txt").unwrap());
+        assert!(!engine.remove("notes.txt").unwrap());
+    }
+
+    #[test]
+    fn test_commit_survives_reopen() {
+        let temp_dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage.path = temp_dir.path().to_string_lossy().to_string();
+
+        {
+            let engine = Engine::open(config.clone()).unwrap();
+            engine.index_data("notes.txt", b"meeting notes", None).unwrap();
+            engine.index_data("todo.txt", b"buy milk", None).unwrap();
+            engine.index_mut().unwrap().index_vector("todo.txt", &[1.0, 0.0]).unwrap();
+            assert_eq!(engine.commit().unwrap(), 2);
+            assert_eq!(engine.commit().unwrap(), 0);
+
+            let index = engine.index().unwrap();
+            assert!(index.payload("notes.txt").is_none());
+            assert!(index.document_text("notes.txt").is_none());
+            drop(index);
+            assert_eq!(engine.search("\"meeting notes\"", 10, 0).unwrap()[0].key, "notes.txt");
+
+            engine.remove("notes.txt").unwrap();
+            assert!(engine.autocomplete("meet", 5).unwrap().is_empty());
+            engine.update("todo.txt", None, Some(HashMap::from([("list".to_string(), "home".to_string())]))).unwrap();
+            assert_eq!(engine.commit().unwrap(), 2);
+            assert_eq!(engine.segments().unwrap().segment_count().unwrap(), 2);
+            engine.index_data("draft.txt", b"flushed on shutdown", None).unwrap();
+            engine.index_data("minutes.txt", b"meeting minutes about milk milk", None).unwrap();
+        }
+
+        let engine = Engine::open(config).unwrap();
+        assert!(engine.search("notes", 10, 0).unwrap().is_empty());
+        assert_eq!(engine.search("shutdown", 10, 0).unwrap()[0].key, "draft.txt");
+        let results = engine.search("milk", 10, 0).unwrap();
+        let mut fresh = SearchIndex::new();
+        fresh.index_data("todo.txt", b"buy milk", None);
+        fresh.index_data("draft.txt", b"flushed on shutdown", None);
+        fresh.index_data("minutes.txt", b"meeting minutes about milk milk", None);
+        let expected = fresh.query("milk", 10).unwrap();
+        assert_eq!(
+            results.iter().map(|r| (&r.key, r.score)).collect::<Vec<_>>(),
+            expected.iter().map(|r| (&r.key, r.score)).collect::<Vec<_>>()
+        );
+
+        assert_eq!(engine.search("\"buy milk\"", 10, 0).unwrap()[0].key, "todo.txt");
+        assert_eq!(engine.autocomplete("bu", 5).unwrap()[0].text, "buy");
+
+        let index = engine.index().unwrap();
+        assert_eq!(index.get_metadata("todo.txt").unwrap().get("list").map(String::as_str), Some("home"));
+        assert_eq!(index.vector_search(&[1.0, 0.0], 1).unwrap()[0].key, "todo.txt");
+        assert!(index.payload("todo.txt").is_none());
+        drop(index);
+
+        assert_eq!(engine.document("todo.txt").unwrap().as_deref(), Some(&b"buy milk".to_vec()));
+        assert_eq!(engine.storage().unwrap().document_count().unwrap(), 3);
+        assert_eq!(engine.load_storage(|_| {}).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_storage_wins_over_stale_segments() {
+        let temp_dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage.path = temp_dir.path().to_string_lossy().to_string();
+
+        {
+            let engine = Engine::open(config.clone()).unwrap();
+            engine.index_data("a.txt", b"alpha original", None).unwrap();
+            engine.index_data("b.txt", b"beta", None).unwrap();
+            engine.index_data("c.txt", b"gamma", None).unwrap();
+            assert_eq!(engine.commit().unwrap(), 3);
+
+            let storage = engine.storage().unwrap();
+            storage.store_document("a.txt", b"alpha revised", r#"{"revision":2}"#).unwrap();
+            storage.delete_document("b.txt").unwrap();
+            storage.store_document("d.txt", b"delta", "{}").unwrap();
+        }
+
+        let engine = Engine::open(config.clone()).unwrap();
+        assert!(engine.search("original", 10, 0).unwrap().is_empty());
+        assert_eq!(engine.search("revised", 10, 0).unwrap()[0].key, "a.txt");
+        assert!(engine.search("beta", 10, 0).unwrap().is_empty());
+        assert_eq!(engine.search("delta", 10, 0).unwrap()[0].key, "d.txt");
+        let index = engine.index().unwrap();
+        assert_eq!(index.get_metadata("a.txt").unwrap()["revision"], "2");
+        assert!(!index.contains("b.txt"));
+        assert_eq!(index.keys().count(), 3);
+        drop(index);
+        assert_eq!(engine.commit().unwrap(), 3);
+        drop(engine);
+
+        let engine = Engine::open(config).unwrap();
+        assert_eq!(engine.load_storage(|_| {}).unwrap(), 0);
+        assert_eq!(engine.commit().unwrap(), 0);
+        assert_eq!(engine.search("revised", 10, 0).unwrap()[0].key, "a.txt");
+        assert!(!engine.index().unwrap().contains("b.txt"));
+    }
+
+    #[test]
+    fn test_periodic_flush() {
+        let temp_dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage.path = temp_dir.path().to_string_lossy().to_string();
+        config.storage.persist_interval_secs = 1;
+
+        let engine = Engine::open(config).unwrap();
+        engine.index_data("notes.txt", b"meeting notes", None).unwrap();
+        let segments = engine.segments().unwrap();
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while segments.segment_count().unwrap() == 0 && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        assert_eq!(segments.segment_count().unwrap(), 1);
+        assert_eq!(engine.commit().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_concurrent_commits_merge_in_order() {
+        let temp_dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage.path = temp_dir.path().to_string_lossy().to_string();
+
+        {
+            let engine = Engine::open(config.clone()).unwrap();
+            std::thread::scope(|scope| {
+                for worker in 0..4 {
+                    let engine = &engine;
+                    scope.spawn(move || {
+                        for round in 0..MAX_SEGMENTS {
+                            let text = format!("round{} shared", round);
+                            engine.index_data("shared.txt", text.as_bytes(), None).unwrap();
+                            engine.index_data(&format!("w{}-{}.txt", worker, round), b"worker note", None).unwrap();
+                            engine.commit().unwrap();
+                        }
+                    });
+                }
+            });
+            engine.index_data("shared.txt", b"final shared", None).unwrap();
+            engine.commit().unwrap();
+
+            let deadline = std::time::Instant::now() + Duration::from_secs(5);
+            while engine.merge_status().unwrap().merges == 0 && std::time::Instant::now() < deadline {
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            let status = engine.merge_status().unwrap();
+            assert!(status.merges > 0);
+            assert_eq!(status.last_error, None);
+        }
+
+        let engine = Engine::open(config).unwrap();
+        assert_eq!(engine.search("worker", 100, 0).unwrap().len(), 4 * MAX_SEGMENTS);
+        assert_eq!(engine.search("final", 10, 0).unwrap()[0].key, "shared.txt");
+        assert!(engine.search("round0", 10, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_storage_rebuild_and_write_through() {
+        let temp_dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage.path = temp_dir.path().to_string_lossy().to_string();
+        config.storage.write_through = true;
+        config.performance.batch_size = 2;
+
+        {
+            let engine = Engine::open(config.clone()).unwrap();
+            let metadata = HashMap::from([("author".to_string(), "ann".to_string())]);
+            engine.index_data("notes.txt", b"meeting notes", Some(metadata)).unwrap();
+            engine
+                .index_batch((0..3).map(|i| (format!("doc-{}.txt", i), b"batch document".to_vec(), None)))
+                .unwrap();
+            engine.remove("doc-2.txt").unwrap();
+            assert_eq!(engine.storage().unwrap().document_count().unwrap(), 3);
+            engine
+                .storage()
+                .unwrap()
+                .store_document("typed.txt", b"typed document", r#"{"year":2019,"draft":true}"#)
+                .unwrap();
+        }
+
+        let mut reports = Vec::new();
+        let engine = Engine::open_with_progress(config, |progress| reports.push(progress)).unwrap();
+        assert_eq!(reports.first(), Some(&LoadProgress { loaded: 0, total: 4 }));
+        assert_eq!(reports.last(), Some(&LoadProgress { loaded: 4, total: 4 }));
+        assert_eq!(reports.len(), 3);
+        assert_eq!(engine.load_storage(|_| {}).unwrap(), 0);
+
+        assert_eq!(engine.search("batch", 10, 0).unwrap().len(), 2);
+        let index = engine.index().unwrap();
+        assert_eq!(index.get_metadata("notes.txt").unwrap().get("author").map(String::as_str), Some("ann"));
+        let typed = index.get_metadata("typed.txt").unwrap();
+        assert_eq!((typed["year"].as_str(), typed["draft"].as_str()), ("2019", "true"));
+        assert!(parse_metadata("list.txt", "[1]").is_err());
+        assert!(!index.contains("doc-2.txt"));
+        assert!(index.payload("notes.txt").is_none());
+        drop(index);
+
+        for _ in 0..2 {
+            assert_eq!(engine.document("notes.txt").unwrap().as_deref(), Some(&b"meeting notes".to_vec()));
+        }
+        assert_eq!(engine.document("missing.txt").unwrap(), None);
+        assert_eq!(engine.index().unwrap().metrics().unwrap().get_cache_hit_rate(), Some(0.5));
+        assert_eq!(engine.cache_stats().unwrap().entries, 1);
+
+        let status = HashMap::from([("status".to_string(), "done".to_string())]);
+        assert!(engine.update("notes.txt", None, Some(status)).unwrap());
+        let stored = engine.storage().unwrap().get_document("notes.txt").unwrap().unwrap();
+        assert_eq!(stored.data, b"meeting notes");
+        assert_eq!(parse_metadata("notes.txt", &stored.metadata).unwrap().unwrap()["status"], "done");
+        assert_eq!(engine.search("\"meeting notes\"", 10, 0).unwrap()[0].key, "notes.txt");
+    }
+
+    #[test]
+    fn test_load_storage_releases_locks_between_batches() {
+        let temp_dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage.path = temp_dir.path().to_string_lossy().to_string();
+        config.performance.batch_size = 2;
+
+        let engine = Engine::open(config).unwrap();
+        let storage = engine.storage().unwrap();
+        for i in 0..4 {
+            storage.store_document(&format!("doc-{}.txt", i), b"stored body", "{}").unwrap();
+        }
+
+        let mut written = false;
+        let loaded = engine
+            .load_storage(|progress| {
+                if progress.loaded > 0 && !written {
+                    assert!(engine.index.try_write().is_ok());
+                    storage.store_document("late.txt", b"written during the scan", "{}").unwrap();
+                    written = true;
+                }
+            })
+            .unwrap();
+        assert_eq!(loaded, 5);
+        assert_eq!(engine.search("scan", 10, 0).unwrap()[0].key, "late.txt");
+    }
+
+    #[test]
+    fn test_search_admission() {
+        let mut config = Config::default();
+        config.performance.max_concurrent_searches = 1;
+        config.performance.search_queue_timeout_ms = 10;
+        let engine = Engine::new(config);
+        engine.index_data("notes.txt", b"meeting notes", None).unwrap();
+
+        let permit = engine.admit().unwrap();
+        assert!(matches!(engine.search("notes", 10, 0), Err(SearchError::Overloaded)));
+        assert_eq!(engine.autocomplete("me", 5), Err(SearchError::Overloaded));
+
+        drop(permit);
+        assert_eq!(engine.search("notes", 10, 0).unwrap().len(), 1);
+    }
+}
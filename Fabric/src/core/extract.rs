@@ -0,0 +1,668 @@
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::path::Path;
+use std::sync::Arc;
+
+use serde_json::Value;
+use zip::ZipArchive;
+
+pub const MIME_TEXT: &str = "text/plain";
+pub const MIME_MARKDOWN: &str = "text/markdown";
+pub const MIME_HTML: &str = "text/html";
+pub const MIME_JSON: &str = "application/json";
+pub const MIME_DOCX: &str = "application/vnd.openxmlformats-officedocument.wordprocessingml.document";
+pub const MIME_ODT: &str = "application/vnd.oasis.opendocument.text";
+pub const MIME_ZIP: &str = "application/zip";
+pub const MIME_BINARY: &str = "application/octet-stream";
+
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+const SNIFF_LENGTH: usize = 1024;
+const MAX_ENTRY_SIZE: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtractedContent {
+    pub mime_type: String,
+    pub text: String,
+    pub metadata: HashMap<String, String>,
+}
+
+pub trait ContentExtractor: Send + Sync {
+    fn mime_types(&self) -> &[&'static str];
+    fn extract(&self, data: &[u8]) -> Result<ExtractedContent, String>;
+}
+
+#[derive(Clone)]
+pub struct ExtractorRegistry {
+    extractors: HashMap<&'static str, Arc<dyn ContentExtractor>>,
+}
+
+impl Default for ExtractorRegistry {
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry.register(PlainTextExtractor);
+        registry.register(MarkdownExtractor);
+        registry.register(HtmlExtractor);
+        registry.register(JsonExtractor);
+        registry.register(OfficeExtractor);
+        registry
+    }
+}
+
+impl ExtractorRegistry {
+    pub fn new() -> Self {
+        ExtractorRegistry {
+            extractors: HashMap::new(),
+        }
+    }
+
+    pub fn register(&mut self, extractor: impl ContentExtractor + 'static) {
+        let extractor: Arc<dyn ContentExtractor> = Arc::new(extractor);
+        for mime_type in extractor.mime_types() {
+            self.extractors.insert(*mime_type, extractor.clone());
+        }
+    }
+
+    pub fn supports(&self, mime_type: &str) -> bool {
+        self.extractors.contains_key(mime_type)
+    }
+
+    pub fn extract(&self, key: &str, data: &[u8]) -> Option<ExtractedContent> {
+        let mime_type = sniff_mime(key, data);
+        let mut content = self.extractors.get(mime_type)?.extract(data).ok()?;
+        content.mime_type = mime_type.to_string();
+        content
+            .metadata
+            .insert("content_type".to_string(), mime_type.to_string());
+        Some(content)
+    }
+
+    pub fn extract_text(&self, key: &str, data: &[u8]) -> Option<String> {
+        match self.extract(key, data) {
+            Some(content) => Some(content.text),
+            None => std::str::from_utf8(data).ok().map(str::to_string),
+        }
+    }
+}
+
+pub fn sniff_mime(key: &str, data: &[u8]) -> &'static str {
+    if data.starts_with(ZIP_MAGIC) {
+        return sniff_zip(data);
+    }
+
+    let text = match decode_text(data) {
+        Some(text) => text,
+        None => return MIME_BINARY,
+    };
+    let head: String = text.trim_start().chars().take(SNIFF_LENGTH).collect::<String>().to_lowercase();
+    let extension = Path::new(key)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .unwrap_or_default();
+
+    if (head.starts_with('{') || head.starts_with('[')) && serde_json::from_str::<Value>(&text).is_ok() {
+        MIME_JSON
+    } else if head.starts_with("<!doctype html")
+        || head.starts_with("<html")
+        || head.contains("<body")
+        || (matches!(extension.as_str(), "html" | "htm") && head.starts_with('<'))
+    {
+        MIME_HTML
+    } else if matches!(extension.as_str(), "md" | "markdown") {
+        MIME_MARKDOWN
+    } else {
+        MIME_TEXT
+    }
+}
+
+fn sniff_zip(data: &[u8]) -> &'static str {
+    let mut archive = match ZipArchive::new(Cursor::new(data)) {
+        Ok(archive) => archive,
+        Err(_) => return MIME_BINARY,
+    };
+
+    if let Ok(mimetype) = read_entry(&mut archive, "mimetype") {
+        if mimetype.trim() == MIME_ODT {
+            return MIME_ODT;
+        }
+    }
+    if archive.by_name("word/document.xml").is_ok() {
+        return MIME_DOCX;
+    }
+    MIME_ZIP
+}
+
+pub fn decode_text(data: &[u8]) -> Option<String> {
+    if let Some(rest) = data.strip_prefix(b"\xEF\xBB\xBF") {
+        return std::str::from_utf8(rest).ok().map(str::to_string);
+    }
+    if let Some(rest) = data.strip_prefix(b"\xFF\xFE") {
+        return decode_utf16(rest, u16::from_le_bytes);
+    }
+    if let Some(rest) = data.strip_prefix(b"\xFE\xFF") {
+        return decode_utf16(rest, u16::from_be_bytes);
+    }
+
+    let text = std::str::from_utf8(data).ok()?;
+    if text.contains('\0') {
+        return None;
+    }
+    Some(text.to_string())
+}
+
+fn decode_utf16(data: &[u8], read: fn([u8; 2]) -> u16) -> Option<String> {
+    if !data.len().is_multiple_of(2) {
+        return None;
+    }
+    let units: Vec<u16> = data.chunks_exact(2).map(|pair| read([pair[0], pair[1]])).collect();
+    String::from_utf16(&units).ok()
+}
+
+fn text_content(data: &[u8]) -> Result<String, String> {
+    decode_text(data).ok_or_else(|| "Content is not valid text".to_string())
+}
+
+pub struct PlainTextExtractor;
+
+impl ContentExtractor for PlainTextExtractor {
+    fn mime_types(&self) -> &[&'static str] {
+        &[MIME_TEXT]
+    }
+
+    fn extract(&self, data: &[u8]) -> Result<ExtractedContent, String> {
+        Ok(ExtractedContent {
+            mime_type: MIME_TEXT.to_string(),
+            text: text_content(data)?,
+            metadata: HashMap::new(),
+        })
+    }
+}
+
+pub struct MarkdownExtractor;
+
+impl ContentExtractor for MarkdownExtractor {
+    fn mime_types(&self) -> &[&'static str] {
+        &[MIME_MARKDOWN]
+    }
+
+    fn extract(&self, data: &[u8]) -> Result<ExtractedContent, String> {
+        let source = text_content(data)?;
+        let mut metadata = HashMap::new();
+        let mut lines = Vec::new();
+        let mut in_code = false;
+
+        for line in source.lines() {
+            let trimmed = line.trim();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                in_code = !in_code;
+                continue;
+            }
+            if in_code {
+                lines.push(line.to_string());
+                continue;
+            }
+
+            let mut line = trimmed;
+            if line.starts_with('#') {
+                let level = line.chars().take_while(|c| *c == '#').count();
+                line = line[level..].trim();
+                if level == 1 && !metadata.contains_key("title") {
+                    metadata.insert("title".to_string(), strip_inline_markdown(line));
+                }
+            }
+            line = line.trim_start_matches('>').trim_start();
+            for marker in ["- ", "* ", "+ "] {
+                if let Some(rest) = line.strip_prefix(marker) {
+                    line = rest;
+                }
+            }
+            if let Some((number, rest)) = line.split_once(". ") {
+                if !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()) {
+                    line = rest;
+                }
+            }
+
+            let line = strip_inline_markdown(line);
+            if !line.is_empty() {
+                lines.push(line);
+            }
+        }
+
+        Ok(ExtractedContent {
+            mime_type: MIME_MARKDOWN.to_string(),
+            text: lines.join("\n"),
+            metadata,
+        })
+    }
+}
+
+fn strip_inline_markdown(line: &str) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut out = String::with_capacity(line.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '!' if chars.get(i + 1) == Some(&'[') => i += 1,
+            '[' => {
+                let close = chars[i..].iter().position(|c| *c == ']').map(|p| p + i);
+                match close {
+                    Some(close) if chars.get(close + 1) == Some(&'(') => {
+                        out.extend(&chars[i + 1..close]);
+                        i = chars[close..]
+                            .iter()
+                            .position(|c| *c == ')')
+                            .map_or(chars.len(), |p| p + close + 1);
+                    }
+                    _ => {
+                        out.push('[');
+                        i += 1;
+                    }
+                }
+            }
+            '*' | '`' => i += 1,
+            '_' if chars.get(i + 1) == Some(&'_') => i += 2,
+            c => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    out.trim().to_string()
+}
+
+pub struct HtmlExtractor;
+
+impl ContentExtractor for HtmlExtractor {
+    fn mime_types(&self) -> &[&'static str] {
+        &[MIME_HTML]
+    }
+
+    fn extract(&self, data: &[u8]) -> Result<ExtractedContent, String> {
+        let source = text_content(data)?;
+        let mut metadata = HashMap::new();
+
+        if let Some(title) = element_text(&source, "title") {
+            metadata.insert("title".to_string(), title);
+        }
+        for (name, content) in meta_tags(&source) {
+            if matches!(name.as_str(), "author" | "description" | "keywords") {
+                metadata.insert(name, content);
+            }
+        }
+
+        let body = match find_ignore_case(&source, "<body") {
+            Some(start) => &source[start..],
+            None => &source[..],
+        };
+        Ok(ExtractedContent {
+            mime_type: MIME_HTML.to_string(),
+            text: strip_markup(body, &HTML_MARKUP),
+            metadata,
+        })
+    }
+}
+
+fn element_text(source: &str, tag: &str) -> Option<String> {
+    let start = find_ignore_case(source, &format!("<{}", tag))?;
+    let open_end = source[start..].find('>')? + start + 1;
+    let close = find_ignore_case(&source[open_end..], &format!("</{}", tag))? + open_end;
+    let text = collapse_whitespace(&decode_entities(&source[open_end..close]));
+    (!text.is_empty()).then_some(text)
+}
+
+fn meta_tags(source: &str) -> Vec<(String, String)> {
+    let mut tags = Vec::new();
+    let mut rest = source;
+    while let Some(start) = find_ignore_case(rest, "<meta") {
+        let end = match rest[start..].find('>') {
+            Some(end) => start + end,
+            None => break,
+        };
+        let tag = &rest[start..end];
+        if let (Some(name), Some(content)) = (attribute(tag, "name"), attribute(tag, "content")) {
+            tags.push((name.to_lowercase(), decode_entities(&content)));
+        }
+        rest = &rest[end..];
+    }
+    tags
+}
+
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut offset = 0;
+    while let Some(pos) = lower[offset..].find(name) {
+        let start = offset + pos;
+        offset = start + name.len();
+        let preceded = lower[..start].ends_with(|c: char| c.is_whitespace());
+        let rest = lower[offset..].trim_start();
+        if !preceded || !rest.starts_with('=') {
+            continue;
+        }
+
+        let value_start = tag.len() - rest.len() + 1;
+        let value = tag[value_start..].trim_start();
+        let quote = value.chars().next()?;
+        return if quote == '"' || quote == '\'' {
+            value[1..].split(quote).next().map(str::to_string)
+        } else {
+            value.split(|c: char| c.is_whitespace() || c == '/').next().map(str::to_string)
+        };
+    }
+    None
+}
+
+pub struct JsonExtractor;
+
+impl ContentExtractor for JsonExtractor {
+    fn mime_types(&self) -> &[&'static str] {
+        &[MIME_JSON]
+    }
+
+    fn extract(&self, data: &[u8]) -> Result<ExtractedContent, String> {
+        let value: Value = serde_json::from_str(&text_content(data)?)
+            .map_err(|e| format!("Failed to parse JSON: {}", e))?;
+
+        let mut metadata = HashMap::new();
+        if let Value::Object(fields) = &value {
+            for (name, field) in fields {
+                let scalar = match field {
+                    Value::String(s) => s.clone(),
+                    Value::Number(n) => n.to_string(),
+                    Value::Bool(b) => b.to_string(),
+                    _ => continue,
+                };
+                metadata.insert(name.clone(), scalar);
+            }
+        }
+
+        let mut strings = Vec::new();
+        collect_strings(&value, &mut strings);
+        Ok(ExtractedContent {
+            mime_type: MIME_JSON.to_string(),
+            text: strings.join("\n"),
+            metadata,
+        })
+    }
+}
+
+fn collect_strings<'a>(value: &'a Value, out: &mut Vec<&'a str>) {
+    match value {
+        Value::String(s) => out.push(s),
+        Value::Array(items) => items.iter().for_each(|item| collect_strings(item, out)),
+        Value::Object(fields) => fields.values().for_each(|field| collect_strings(field, out)),
+        _ => {}
+    }
+}
+
+pub struct OfficeExtractor;
+
+impl ContentExtractor for OfficeExtractor {
+    fn mime_types(&self) -> &[&'static str] {
+        &[MIME_DOCX, MIME_ODT]
+    }
+
+    fn extract(&self, data: &[u8]) -> Result<ExtractedContent, String> {
+        let mut archive =
+            ZipArchive::new(Cursor::new(data)).map_err(|e| format!("Failed to open archive: {}", e))?;
+
+        let (mime_type, body, properties) = if archive.by_name("word/document.xml").is_ok() {
+            (MIME_DOCX, "word/document.xml", "docProps/core.xml")
+        } else {
+            (MIME_ODT, "content.xml", "meta.xml")
+        };
+
+        let text = strip_markup(&read_entry(&mut archive, body)?, &OFFICE_MARKUP);
+        let mut metadata = HashMap::new();
+        if let Ok(properties) = read_entry(&mut archive, properties) {
+            for (tag, field) in [
+                ("dc:title", "title"),
+                ("dc:creator", "author"),
+                ("meta:initial-creator", "author"),
+                ("dc:subject", "subject"),
+            ] {
+                if metadata.contains_key(field) {
+                    continue;
+                }
+                if let Some(value) = element_text(&properties, tag) {
+                    metadata.insert(field.to_string(), value);
+                }
+            }
+        }
+
+        Ok(ExtractedContent {
+            mime_type: mime_type.to_string(),
+            text,
+            metadata,
+        })
+    }
+}
+
+fn read_entry(archive: &mut ZipArchive<Cursor<&[u8]>>, name: &str) -> Result<String, String> {
+    let entry = archive
+        .by_name(name)
+        .map_err(|e| format!("Missing archive entry {}: {}", name, e))?;
+    let mut contents = String::new();
+    entry
+        .take(MAX_ENTRY_SIZE)
+        .read_to_string(&mut contents)
+        .map_err(|e| format!("Failed to read archive entry {}: {}", name, e))?;
+    Ok(contents)
+}
+
+struct MarkupRules {
+    breaks: &'static [&'static str],
+    spaces: &'static [&'static str],
+    skip: &'static [&'static str],
+}
+
+const HTML_MARKUP: MarkupRules = MarkupRules {
+    breaks: &[
+        "p", "div", "br", "li", "tr", "h1", "h2", "h3", "h4", "h5", "h6", "section", "article", "header",
+        "footer", "blockquote", "pre", "table", "ul", "ol",
+    ],
+    spaces: &["td", "th", "img"],
+    skip: &["script", "style", "noscript", "template", "head"],
+};
+
+const OFFICE_MARKUP: MarkupRules = MarkupRules {
+    breaks: &["w:p", "w:br", "w:cr", "text:p", "text:h", "text:line-break", "text:list-item"],
+    spaces: &["w:tab", "text:s", "text:tab"],
+    skip: &["w:instrtext", "w:delinstrtext", "office:annotation"],
+};
+
+fn strip_markup(markup: &str, rules: &MarkupRules) -> String {
+    let mut text = String::with_capacity(markup.len() / 2);
+    let mut rest = markup;
+
+    while let Some(open) = rest.find('<') {
+        text.push_str(&rest[..open]);
+        rest = &rest[open..];
+
+        if rest.starts_with("<!--") {
+            rest = rest.find("-->").map_or("", |end| &rest[end + 3..]);
+            continue;
+        }
+
+        let close = match rest.find('>') {
+            Some(close) => close,
+            None => break,
+        };
+        let tag = &rest[1..close];
+        rest = &rest[close + 1..];
+
+        let closing = tag.starts_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+
+        if !closing && !tag.ends_with('/') && rules.skip.contains(&name.as_str()) {
+            rest = find_ignore_case(rest, &format!("</{}", name))
+                .and_then(|end| rest[end..].find('>').map(|gt| &rest[end + gt + 1..]))
+                .unwrap_or("");
+        } else if rules.breaks.contains(&name.as_str()) {
+            text.push('\n');
+        } else if rules.spaces.contains(&name.as_str()) {
+            text.push(' ');
+        }
+    }
+    if !rest.starts_with('<') {
+        text.push_str(rest);
+    }
+
+    decode_entities(&text)
+        .lines()
+        .map(collapse_whitespace)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    let needle = needle.as_bytes();
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle))
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        let end = rest.bytes().take(12).position(|b| b == b';');
+        let decoded = end.and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, end))
+        });
+
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::SearchIndex;
+    use std::io::Write;
+    use zip::write::FileOptions;
+    use zip::ZipWriter;
+
+    fn archive(entries: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, contents) in entries {
+            writer.start_file(*name, FileOptions::default()).unwrap();
+            writer.write_all(contents.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_text_formats() {
+        let registry = ExtractorRegistry::default();
+
+        let html = b"<!DOCTYPE html><html><head><title>Q3 &amp; Q4</title>\
+            <meta name=\"author\" content=\"Dana\"><style>p { color: red }</style></head>\
+            <body><h1>Report</h1><p>Revenue <b>grew</b> fast</p><script>track()</script></body></html>";
+        let content = registry.extract("page", html).unwrap();
+        assert_eq!(content.mime_type, MIME_HTML);
+        assert_eq!(content.text, "Report\nRevenue grew fast");
+        assert_eq!(content.metadata["title"], "Q3 & Q4");
+        assert_eq!(content.metadata["author"], "Dana");
+
+        let markdown = b"# Release Notes\n\n- Added **search** via [the index](http://x)\n```\nlet x = 1;\n```";
+        let content = registry.extract("notes.md", markdown).unwrap();
+        assert_eq!(content.text, "Release Notes\nAdded search via the index\nlet x = 1;");
+        assert_eq!(content.metadata["title"], "Release Notes");
+
+        let json = br#"{"name": "fabric", "version": 2, "tags": ["search", "index"]}"#;
+        let content = registry.extract("package", json).unwrap();
+        assert_eq!(content.mime_type, MIME_JSON);
+        assert!(content.text.contains("fabric") && content.text.contains("index"));
+        assert_eq!(content.metadata["version"], "2");
+
+        assert_eq!(sniff_mime("a.txt", b"\xFF\xFEh\0i\0"), MIME_TEXT);
+        assert_eq!(registry.extract("a.txt", b"\xFF\xFEh\0i\0").unwrap().text, "hi");
+        assert!(registry.extract("blob", &[0, 159, 146, 150]).is_none());
+    }
+
+    #[test]
+    fn test_office_documents() {
+        let docx = archive(&[
+            ("[Content_Types].xml", "<Types/>"),
+            (
+                "word/document.xml",
+                "<w:document><w:body><w:p><w:r><w:t>Quarterly</w:t></w:r><w:r><w:t xml:space=\"preserve\"> plan</w:t></w:r></w:p>\
+                 <w:p><w:r><w:t>Budget &amp; hiring</w:t></w:r></w:p></w:body></w:document>",
+            ),
+            ("docProps/core.xml", "<cp:coreProperties><dc:title>Plan</dc:title><dc:creator>Ana</dc:creator></cp:coreProperties>"),
+        ]);
+        assert_eq!(sniff_mime("plan.docx", &docx), MIME_DOCX);
+
+        let registry = ExtractorRegistry::default();
+        let content = registry.extract("plan.docx", &docx).unwrap();
+        assert_eq!(content.text, "Quarterly plan\nBudget & hiring");
+        assert_eq!(content.metadata["title"], "Plan");
+        assert_eq!(content.metadata["author"], "Ana");
+
+        let mut index = SearchIndex::new();
+        index.index_data("plan.docx", &docx, None);
+        let results = index.query("\"budget hiring\"", 10).unwrap();
+        assert_eq!(results[0].key, "plan.docx");
+        assert_eq!(results[0].metadata.as_ref().unwrap()["content_type"], MIME_DOCX);
+        assert_eq!(index.autocomplete("quart", 1)[0].text, "quarterly");
+
+        let odt = archive(&[
+            ("mimetype", MIME_ODT),
+            (
+                "content.xml",
+                "<office:document-content><office:body><office:text><text:h>Minutes</text:h>\
+                 <text:p>Agreed<text:s/>on roadmap</text:p></office:text></office:body></office:document-content>",
+            ),
+            ("meta.xml", "<office:meta><meta:initial-creator>Li</meta:initial-creator></office:meta>"),
+        ]);
+        let content = registry.extract("minutes.odt", &odt).unwrap();
+        assert_eq!(content.mime_type, MIME_ODT);
+        assert_eq!(content.text, "Minutes\nAgreed on roadmap");
+        assert_eq!(content.metadata["author"], "Li");
+
+        assert_eq!(sniff_mime("x.zip", &archive(&[("a.txt", "a")])), MIME_ZIP);
+        assert!(registry.extract("x.zip", &archive(&[("a.txt", "a")])).is_none());
+    }
+}
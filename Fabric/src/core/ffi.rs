@@ -0,0 +1,702 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::path::{Component, Path};
+use std::ptr;
+use std::sync::{Arc, Mutex, Weak};
+
+use lazy_static::lazy_static;
+
+use crate::config;
+use crate::engine::{Engine, SearchError};
+use crate::search::{SearchResult, Suggestion};
+
+lazy_static! {
+    static ref ENGINES: Mutex<HashMap<String, OpenEngine>> = Mutex::new(HashMap::new());
+}
+
+thread_local! {
+    static LAST_ERROR: Cell<FabricError> = const { Cell::new(FabricError::None) };
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FabricError {
+    None = 0,
+    InvalidArgument = 1,
+    Failed = 2,
+    Overloaded = 3,
+}
+
+impl From<&SearchError> for FabricError {
+    fn from(error: &SearchError) -> Self {
+        match error {
+            SearchError::Overloaded => FabricError::Overloaded,
+            SearchError::Failed(_) => FabricError::Failed,
+        }
+    }
+}
+
+fn set_last_error(error: FabricError) {
+    LAST_ERROR.with(|last| last.set(error));
+}
+
+fn report(result: Result<bool, String>) -> bool {
+    match result {
+        Ok(changed) => {
+            set_last_error(FabricError::None);
+            changed
+        }
+        Err(_) => {
+            set_last_error(FabricError::Failed);
+            false
+        }
+    }
+}
+
+struct OpenEngine {
+    engine: Weak<Engine>,
+    config_path: Option<String>,
+}
+
+pub struct FabricHandle {
+    name: String,
+    engine: Arc<Engine>,
+}
+
+pub struct FabricResultSet {
+    entries: Vec<ResultEntry>,
+}
+
+struct ResultEntry {
+    key: CString,
+    score: f32,
+    metadata_json: CString,
+    positions: Vec<usize>,
+}
+
+impl FabricResultSet {
+    fn from_results(results: Vec<SearchResult>) -> Self {
+        let entries = results
+            .into_iter()
+            .filter_map(|result| {
+                let metadata_json = serde_json::to_string(&result.metadata.unwrap_or_default()).ok()?;
+                Some(ResultEntry {
+                    key: CString::new(result.key).ok()?,
+                    score: result.score,
+                    metadata_json: CString::new(metadata_json).ok()?,
+                    positions: result.positions,
+                })
+            })
+            .collect();
+        FabricResultSet { entries }
+    }
+
+    fn from_suggestions(suggestions: Vec<Suggestion>, prefix: &str) -> Self {
+        let matched = prefix.trim().chars().count();
+        let entries = suggestions
+            .into_iter()
+            .filter_map(|suggestion| {
+                Some(ResultEntry {
+                    positions: (0..matched.min(suggestion.text.chars().count())).collect(),
+                    key: CString::new(suggestion.text).ok()?,
+                    score: suggestion.score,
+                    metadata_json: CString::new("{}").ok()?,
+                })
+            })
+            .collect();
+        FabricResultSet { entries }
+    }
+
+    unsafe fn entry<'a>(results: *const FabricResultSet, index: usize) -> Option<&'a ResultEntry> {
+        results.as_ref().and_then(|set| set.entries.get(index))
+    }
+}
+
+unsafe fn engine<'a>(handle: *const FabricHandle) -> Option<&'a Engine> {
+    handle.as_ref().map(|handle| handle.engine.as_ref())
+}
+
+fn open_engine(name: &str, config_path: Option<&str>) -> Result<Arc<Engine>, String> {
+    let mut components = Path::new(name).components();
+    if !matches!((components.next(), components.next()), (Some(Component::Normal(_)), None)) {
+        return Err(format!("Invalid engine name: {}", name));
+    }
+
+    let mut engines = ENGINES
+        .lock()
+        .map_err(|_| "Failed to acquire engine registry lock".to_string())?;
+    engines.retain(|_, open| open.engine.strong_count() > 0);
+
+    if let Some(open) = engines.get(name) {
+        if let Some(engine) = open.engine.upgrade() {
+            if open.config_path.as_deref() != config_path {
+                return Err(format!("Engine {} is already open with a different configuration", name));
+            }
+            return Ok(engine);
+        }
+    }
+
+    let mut config = match config_path {
+        Some(path) => config::load_config(path)?,
+        None => config::get_config().unwrap_or_default(),
+    };
+    config.storage.path = Path::new(&config.storage.path)
+        .join(name)
+        .to_string_lossy()
+        .to_string();
+
+    let engine = match config_path {
+        Some(_) => Arc::new(Engine::open(config)?),
+        None => Arc::new(Engine::new(config)),
+    };
+    engines.insert(
+        name.to_string(),
+        OpenEngine {
+            engine: Arc::downgrade(&engine),
+            config_path: config_path.map(str::to_string),
+        },
+    );
+    Ok(engine)
+}
+
+unsafe fn str_arg<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    CStr::from_ptr(s).to_str().ok()
+}
+
+unsafe fn metadata_arg(json: *const c_char) -> Result<Option<HashMap<String, String>>, ()> {
+    if json.is_null() {
+        return Ok(None);
+    }
+    let json = str_arg(json).ok_or(())?;
+    serde_json::from_str(json).map(Some).map_err(|_| ())
+}
+
+/// # Safety
+///
+/// `name` and `config_path` must be null or valid NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn fabric_open(name: *const c_char, config_path: *const c_char) -> *mut FabricHandle {
+    let name_str = match str_arg(name) {
+        Some(s) if !s.is_empty() => s,
+        _ => return ptr::null_mut(),
+    };
+    let config_path = if config_path.is_null() {
+        None
+    } else {
+        match str_arg(config_path) {
+            Some(s) => Some(s),
+            None => return ptr::null_mut(),
+        }
+    };
+
+    match open_engine(name_str, config_path) {
+        Ok(engine) => Box::into_raw(Box::new(FabricHandle {
+            name: name_str.to_string(),
+            engine,
+        })),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// # Safety
+///
+/// `handle` must be null or a handle from `fabric_open` that has not been closed yet.
+#[no_mangle]
+pub unsafe extern "C" fn fabric_close(handle: *mut FabricHandle) {
+    if handle.is_null() {
+        return;
+    }
+
+    let handle = Box::from_raw(handle);
+    let FabricHandle { name, engine } = *handle;
+
+    let engines = ENGINES.lock();
+    drop(engine);
+    if let Ok(mut engines) = engines {
+        if engines.get(&name).is_some_and(|open| open.engine.strong_count() == 0) {
+            engines.remove(&name);
+        }
+    }
+}
+
+/// # Safety
+///
+/// `handle` must be null or a live handle, `key` must be null or a valid NUL-terminated
+/// string, and `data` must point to `length` readable bytes when `length` is non-zero.
+#[no_mangle]
+pub unsafe extern "C" fn fabric_index_data(
+    handle: *mut FabricHandle,
+    key: *const c_char,
+    data: *const u8,
+    length: usize,
+) -> bool {
+    let (engine, key_str) = match (engine(handle), str_arg(key)) {
+        (Some(engine), Some(key_str)) if !data.is_null() || length == 0 => (engine, key_str),
+        _ => {
+            set_last_error(FabricError::InvalidArgument);
+            return false;
+        }
+    };
+
+    let data_slice = if length == 0 {
+        &[][..]
+    } else {
+        std::slice::from_raw_parts(data, length)
+    };
+    report(engine.index_data(key_str, data_slice, None))
+}
+
+/// # Safety
+///
+/// `handle` must be null or a live handle and `key` must be null or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn fabric_remove(handle: *mut FabricHandle, key: *const c_char) -> bool {
+    match (engine(handle), str_arg(key)) {
+        (Some(engine), Some(key_str)) => report(engine.remove(key_str)),
+        _ => {
+            set_last_error(FabricError::InvalidArgument);
+            false
+        }
+    }
+}
+
+/// # Safety
+///
+/// `handle` must be null or a live handle, `key` and `metadata_json` must be null or valid
+/// NUL-terminated strings, and `data` must be null or point to `length` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn fabric_update(
+    handle: *mut FabricHandle,
+    key: *const c_char,
+    data: *const u8,
+    length: usize,
+    metadata_json: *const c_char,
+) -> bool {
+    let (engine, key_str, metadata) = match (engine(handle), str_arg(key), metadata_arg(metadata_json)) {
+        (Some(engine), Some(key_str), Ok(metadata)) => (engine, key_str, metadata),
+        _ => {
+            set_last_error(FabricError::InvalidArgument);
+            return false;
+        }
+    };
+
+    let data_slice = if data.is_null() {
+        None
+    } else {
+        Some(std::slice::from_raw_parts(data, length))
+    };
+    report(engine.update(key_str, data_slice, metadata))
+}
+
+/// # Safety
+///
+/// `handle` must be null or a live handle, `query` must be null or a valid NUL-terminated
+/// string, and `result` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn fabric_search(
+    handle: *mut FabricHandle,
+    query: *const c_char,
+    result: *mut *mut c_char,
+) -> bool {
+    let (engine, query_str) = match (engine(handle), str_arg(query)) {
+        (Some(engine), Some(query_str)) if !result.is_null() => (engine, query_str),
+        _ => {
+            set_last_error(FabricError::InvalidArgument);
+            return false;
+        }
+    };
+
+    let results = match engine.search(query_str, 1, 0) {
+        Ok(results) => results,
+        Err(error) => {
+            set_last_error(FabricError::from(&error));
+            return false;
+        }
+    };
+    set_last_error(FabricError::None);
+    match results.into_iter().next().and_then(|r| CString::new(r.key).ok()) {
+        Some(cstring) => {
+            *result = cstring.into_raw();
+            true
+        }
+        None => false,
+    }
+}
+
+/// # Safety
+///
+/// `handle` must be null or a live handle and `query` must be null or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn fabric_search_ex(
+    handle: *mut FabricHandle,
+    query: *const c_char,
+    limit: usize,
+    offset: usize,
+) -> *mut FabricResultSet {
+    let (engine, query_str) = match (engine(handle), str_arg(query)) {
+        (Some(engine), Some(query_str)) => (engine, query_str),
+        _ => {
+            set_last_error(FabricError::InvalidArgument);
+            return ptr::null_mut();
+        }
+    };
+
+    match engine.search(query_str, limit, offset) {
+        Ok(results) => {
+            set_last_error(FabricError::None);
+            Box::into_raw(Box::new(FabricResultSet::from_results(results)))
+        }
+        Err(error) => {
+            set_last_error(FabricError::from(&error));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// # Safety
+///
+/// `handle` must be null or a live handle and `prefix` must be null or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn fabric_autocomplete(
+    handle: *mut FabricHandle,
+    prefix: *const c_char,
+    limit: usize,
+) -> *mut FabricResultSet {
+    let (engine, prefix_str) = match (engine(handle), str_arg(prefix)) {
+        (Some(engine), Some(prefix_str)) => (engine, prefix_str),
+        _ => {
+            set_last_error(FabricError::InvalidArgument);
+            return ptr::null_mut();
+        }
+    };
+
+    match engine.autocomplete(prefix_str, limit) {
+        Ok(suggestions) => {
+            set_last_error(FabricError::None);
+            Box::into_raw(Box::new(FabricResultSet::from_suggestions(suggestions, prefix_str)))
+        }
+        Err(error) => {
+            set_last_error(FabricError::from(&error));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// # Safety
+///
+/// `results` must be null or a live result set returned by this library.
+#[no_mangle]
+pub unsafe extern "C" fn fabric_result_count(results: *const FabricResultSet) -> usize {
+    results.as_ref().map_or(0, |set| set.entries.len())
+}
+
+/// # Safety
+///
+/// `results` must be null or a live result set returned by this library.
+#[no_mangle]
+pub unsafe extern "C" fn fabric_result_get_key(results: *const FabricResultSet, index: usize) -> *const c_char {
+    FabricResultSet::entry(results, index).map_or(ptr::null(), |entry| entry.key.as_ptr())
+}
+
+/// # Safety
+///
+/// `results` must be null or a live result set returned by this library.
+#[no_mangle]
+pub unsafe extern "C" fn fabric_result_get_score(results: *const FabricResultSet, index: usize) -> f32 {
+    FabricResultSet::entry(results, index).map_or(0.0, |entry| entry.score)
+}
+
+/// # Safety
+///
+/// `results` must be null or a live result set returned by this library.
+#[no_mangle]
+pub unsafe extern "C" fn fabric_result_get_metadata_json(
+    results: *const FabricResultSet,
+    index: usize,
+) -> *const c_char {
+    FabricResultSet::entry(results, index).map_or(ptr::null(), |entry| entry.metadata_json.as_ptr())
+}
+
+/// # Safety
+///
+/// `results` must be null or a live result set returned by this library and `count` must be
+/// null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn fabric_result_get_positions(
+    results: *const FabricResultSet,
+    index: usize,
+    count: *mut usize,
+) -> *const usize {
+    let positions = FabricResultSet::entry(results, index).map_or(&[][..], |entry| &entry.positions[..]);
+    if !count.is_null() {
+        *count = positions.len();
+    }
+    if positions.is_empty() {
+        ptr::null()
+    } else {
+        positions.as_ptr()
+    }
+}
+
+/// # Safety
+///
+/// `results` must be null or a result set returned by this library that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn fabric_result_free(results: *mut FabricResultSet) {
+    if !results.is_null() {
+        drop(Box::from_raw(results));
+    }
+}
+
+/// # Safety
+///
+/// `handle` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn fabric_flush(handle: *mut FabricHandle) -> bool {
+    let engine = match engine(handle) {
+        Some(engine) => engine,
+        None => {
+            set_last_error(FabricError::InvalidArgument);
+            return false;
+        }
+    };
+
+    match engine.commit() {
+        Ok(_) => {
+            set_last_error(FabricError::None);
+            true
+        }
+        Err(_) => {
+            set_last_error(FabricError::Failed);
+            false
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn fabric_last_error() -> FabricError {
+    LAST_ERROR.with(Cell::get)
+}
+
+/// # Safety
+///
+/// `s` must be null or a string returned by this library that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn fabric_free_string(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_independent_handles() {
+        unsafe {
+            let first_name = CString::new("ffi-test-first").unwrap();
+            let second_name = CString::new("ffi-test-second").unwrap();
+            let first = fabric_open(first_name.as_ptr(), ptr::null());
+            let second = fabric_open(second_name.as_ptr(), ptr::null());
+            let shared = fabric_open(first_name.as_ptr(), ptr::null());
+            assert!(!first.is_null() && !second.is_null() && !shared.is_null());
+
+            let key = CString::new("report.txt").unwrap();
+            let data = b"quarterly numbers";
+            assert!(fabric_index_data(first, key.as_ptr(), data.as_ptr(), data.len()));
+            assert!(!fabric_index_data(first, ptr::null(), data.as_ptr(), data.len()));
+            assert_eq!(fabric_last_error(), FabricError::InvalidArgument);
+            let bad_metadata = CString::new("not json").unwrap();
+            assert!(!fabric_update(first, key.as_ptr(), ptr::null(), 0, bad_metadata.as_ptr()));
+            assert_eq!(fabric_last_error(), FabricError::InvalidArgument);
+            assert!(!fabric_remove(second, key.as_ptr()));
+            assert_eq!(fabric_last_error(), FabricError::None);
+
+            let query = CString::new("quarterly").unwrap();
+            let results = fabric_search_ex(shared, query.as_ptr(), 10, 0);
+            assert_eq!(fabric_result_count(results), 1);
+
+            let mut count = 0;
+            let positions = fabric_result_get_positions(results, 0, &mut count);
+            assert_eq!(count, 0);
+            assert!(positions.is_null());
+            fabric_result_free(results);
+
+            let key_query = CString::new("report").unwrap();
+            let results = fabric_search_ex(first, key_query.as_ptr(), 10, 0);
+            let positions = fabric_result_get_positions(results, 0, &mut count);
+            let positions = std::slice::from_raw_parts(positions, count);
+            assert_eq!(positions, &[0, 1, 2, 3, 4, 5]);
+            fabric_result_free(results);
+
+            let results = fabric_search_ex(second, query.as_ptr(), 10, 0);
+            assert_eq!(fabric_result_count(results), 0);
+            assert_eq!(fabric_last_error(), FabricError::None);
+            fabric_result_free(results);
+            assert!(fabric_search_ex(second, ptr::null(), 10, 0).is_null());
+            assert_eq!(fabric_last_error(), FabricError::InvalidArgument);
+
+            let prefix = CString::new("quar").unwrap();
+            let suggestions = fabric_autocomplete(shared, prefix.as_ptr(), 5);
+            assert_eq!(fabric_result_count(suggestions), 1);
+            let text = CStr::from_ptr(fabric_result_get_key(suggestions, 0));
+            assert_eq!(text.to_str().unwrap(), "quarterly");
+            fabric_result_free(suggestions);
+
+            fabric_close(first);
+            fabric_close(shared);
+            fabric_close(second);
+
+            let reopened = fabric_open(first_name.as_ptr(), ptr::null());
+            let results = fabric_search_ex(reopened, query.as_ptr(), 10, 0);
+            assert_eq!(fabric_result_count(results), 0);
+            fabric_result_free(results);
+            fabric_close(reopened);
+
+            assert!(fabric_open(ptr::null(), ptr::null()).is_null());
+            for escaping in ["../outside", "/tmp/outside", "nested/name", ".."] {
+                let escaping = CString::new(escaping).unwrap();
+                assert!(fabric_open(escaping.as_ptr(), ptr::null()).is_null());
+            }
+
+            let in_memory = fabric_open(second_name.as_ptr(), ptr::null());
+            assert!(!fabric_flush(in_memory));
+            assert_eq!(fabric_last_error(), FabricError::Failed);
+            fabric_close(in_memory);
+        }
+    }
+
+    unsafe fn result_keys(results: *const FabricResultSet) -> Vec<String> {
+        (0..fabric_result_count(results))
+            .map(|index| CStr::from_ptr(fabric_result_get_key(results, index)).to_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_search_ex_pages_results() {
+        unsafe {
+            let name = CString::new("ffi-test-paging").unwrap();
+            let handle = fabric_open(name.as_ptr(), ptr::null());
+            for i in 0..5 {
+                let key = CString::new(format!("invoice{}.txt", i)).unwrap();
+                let data = format!("invoice {}", "total ".repeat(i + 1));
+                assert!(fabric_index_data(handle, key.as_ptr(), data.as_ptr(), data.len()));
+            }
+
+            let query = CString::new("invoice").unwrap();
+            let mut pages = Vec::new();
+            for offset in [0, 2, 4] {
+                let results = fabric_search_ex(handle, query.as_ptr(), 2, offset);
+                assert_eq!(fabric_last_error(), FabricError::None);
+                pages.extend(result_keys(results));
+                fabric_result_free(results);
+            }
+            let all = fabric_search_ex(handle, query.as_ptr(), 10, 0);
+            assert_eq!(pages, result_keys(all));
+            assert_eq!(pages.len(), 5);
+            assert!(fabric_result_get_score(all, 0) >= fabric_result_get_score(all, 4));
+            let metadata = CStr::from_ptr(fabric_result_get_metadata_json(all, 0));
+            assert!(serde_json::from_str::<HashMap<String, String>>(metadata.to_str().unwrap()).is_ok());
+
+            let mut count = 7;
+            assert!(fabric_result_get_key(all, 5).is_null());
+            assert!(fabric_result_get_metadata_json(all, 5).is_null());
+            assert_eq!(fabric_result_get_score(all, 5), 0.0);
+            assert!(fabric_result_get_positions(all, 5, &mut count).is_null());
+            assert_eq!(count, 0);
+            fabric_result_free(all);
+
+            let beyond = fabric_search_ex(handle, query.as_ptr(), 10, 50);
+            assert!(!beyond.is_null());
+            assert_eq!(fabric_result_count(beyond), 0);
+            assert_eq!(fabric_last_error(), FabricError::None);
+            fabric_result_free(beyond);
+
+            assert!(fabric_search_ex(ptr::null_mut(), query.as_ptr(), 10, 0).is_null());
+            assert_eq!(fabric_last_error(), FabricError::InvalidArgument);
+            assert_eq!(fabric_result_count(ptr::null()), 0);
+            assert!(fabric_result_get_key(ptr::null(), 0).is_null());
+            fabric_result_free(ptr::null_mut());
+            fabric_close(handle);
+        }
+    }
+
+    #[test]
+    fn test_flush_persists_configured_engine() {
+        unsafe {
+            let temp_dir = tempfile::tempdir().unwrap();
+            let mut config = config::Config::default();
+            config.storage.path = temp_dir.path().to_string_lossy().to_string();
+            let config_path = temp_dir.path().join("fabric.toml").to_string_lossy().to_string();
+            config::save_config(&config_path, &config).unwrap();
+
+            let name = CString::new("ffi-test-flush").unwrap();
+            let config_path = CString::new(config_path).unwrap();
+            let handle = fabric_open(name.as_ptr(), config_path.as_ptr());
+            assert!(fabric_open(name.as_ptr(), ptr::null()).is_null());
+            let key = CString::new("report.txt").unwrap();
+            let data = b"quarterly numbers";
+            assert!(fabric_index_data(handle, key.as_ptr(), data.as_ptr(), data.len()));
+            assert!(fabric_flush(handle));
+            assert!(temp_dir.path().join("ffi-test-flush").join("index").join("MANIFEST").exists());
+            fabric_close(handle);
+
+            assert!(!fabric_flush(ptr::null_mut()));
+            assert_eq!(fabric_last_error(), FabricError::InvalidArgument);
+
+            let reopened = fabric_open(name.as_ptr(), config_path.as_ptr());
+            let query = CString::new("quarterly").unwrap();
+            let results = fabric_search_ex(reopened, query.as_ptr(), 10, 0);
+            assert_eq!(fabric_result_count(results), 1);
+            fabric_result_free(results);
+            fabric_close(reopened);
+        }
+    }
+    #[test]
+    fn test_reopen_waits_for_close_to_commit() {
+        unsafe {
+            let temp_dir = tempfile::tempdir().unwrap();
+            let mut config = config::Config::default();
+            config.storage.path = temp_dir.path().to_string_lossy().to_string();
+            let config_path = temp_dir.path().join("fabric.toml").to_string_lossy().to_string();
+            config::save_config(&config_path, &config).unwrap();
+
+            let name = CString::new("ffi-test-reopen").unwrap();
+            let config_path = CString::new(config_path).unwrap();
+            let handle = fabric_open(name.as_ptr(), config_path.as_ptr());
+            for i in 0..500 {
+                let key = CString::new(format!("ledger{}.txt", i)).unwrap();
+                let data = format!("ledger entry {}", i);
+                assert!(fabric_index_data(handle, key.as_ptr(), data.as_ptr(), data.len()));
+            }
+
+            let address = handle as usize;
+            let closer = std::thread::spawn(move || fabric_close(address as *mut FabricHandle));
+            loop {
+                let closed = ENGINES
+                    .lock()
+                    .unwrap()
+                    .get("ffi-test-reopen")
+                    .is_none_or(|open| open.engine.strong_count() == 0);
+                if closed {
+                    break;
+                }
+                std::thread::yield_now();
+            }
+
+            let reopened = fabric_open(name.as_ptr(), config_path.as_ptr());
+            closer.join().unwrap();
+            let query = CString::new("ledger").unwrap();
+            let results = fabric_search_ex(reopened, query.as_ptr(), 1000, 0);
+            assert_eq!(fabric_result_count(results), 500);
+            fabric_result_free(results);
+            fabric_close(reopened);
+        }
+    }
+}
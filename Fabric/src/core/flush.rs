@@ -0,0 +1,60 @@
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+pub struct Flusher {
+    sender: Option<Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Flusher {
+    pub fn spawn<F>(interval: Duration, flush: F) -> Self
+    where
+        F: Fn() + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel::<()>();
+        let handle = thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = receiver.recv_timeout(interval) {
+                flush();
+            }
+        });
+        Flusher {
+            sender: Some(sender),
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for Flusher {
+    fn drop(&mut self) {
+        self.sender.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_flushes_on_interval_until_dropped() {
+        let flushes = Arc::new(AtomicUsize::new(0));
+        let flusher = {
+            let flushes = flushes.clone();
+            Flusher::spawn(Duration::from_millis(10), move || {
+                flushes.fetch_add(1, Ordering::SeqCst);
+            })
+        };
+        thread::sleep(Duration::from_millis(100));
+        drop(flusher);
+
+        let count = flushes.load(Ordering::SeqCst);
+        assert!(count >= 2);
+        thread::sleep(Duration::from_millis(30));
+        assert_eq!(flushes.load(Ordering::SeqCst), count);
+    }
+}
@@ -1,15 +1,40 @@
+use lazy_static::lazy_static;
 use rusqlite::{params, Connection, Result as SqliteResult, NO_PARAMS};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-const CURRENT_SCHEMA_VERSION: u32 = 1;
+use crate::extract::ExtractorRegistry;
+
+mod batch;
+mod cache;
+mod metadata;
+mod migrations;
+
+pub use batch::{DocumentCursor, StorageTransaction};
+pub use cache::{CacheStats, CachedStorage, LruCache};
+pub use metadata::MetadataQuery;
+pub use migrations::{Migration, MIGRATIONS};
+
+pub const CURRENT_SCHEMA_VERSION: u32 = 3;
+
+const SNIPPET_START: &str = "<b>";
+const SNIPPET_END: &str = "</b>";
+const SNIPPET_ELLIPSIS: &str = "...";
+const SNIPPET_TOKENS: i64 = 12;
+const FALLBACK_SNIPPET_CHARS: usize = 48;
+
+lazy_static! {
+    static ref EXTRACTORS: ExtractorRegistry = ExtractorRegistry::default();
+}
 
 #[derive(Debug)]
 pub struct Storage {
     conn: Arc<Mutex<Connection>>,
     path: String,
+    full_text: bool,
+    metadata_columns: Arc<Mutex<Vec<(String, String)>>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -21,67 +46,49 @@ pub struct StoredDocument {
     pub updated_at: i64,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DocumentMatch {
+    pub document: StoredDocument,
+    pub score: f64,
+    pub snippet: String,
+}
+
 impl Storage {
     pub fn new<P: AsRef<Path>>(path: P) -> SqliteResult<Self> {
         let conn = Connection::open(&path)?;
-        let storage = Storage {
+        let mut storage = Storage {
             conn: Arc::new(Mutex::new(conn)),
             path: path.as_ref().to_string_lossy().to_string(),
+            full_text: false,
+            metadata_columns: Arc::new(Mutex::new(Vec::new())),
         };
         
-        storage.init_db()?;
+        storage.full_text = storage.init_db()?;
         Ok(storage)
     }
     
-    fn init_db(&self) -> SqliteResult<()> {
-        let conn = self.conn.lock().unwrap();
+    fn init_db(&self) -> SqliteResult<bool> {
+        let mut conn = self.conn.lock().unwrap();
         
         conn.pragma_update(None, "journal_mode", &"WAL")?;
         conn.pragma_update(None, "synchronous", &"NORMAL")?;
         
-        let tx = conn.unchecked_transaction()?;
-        
-        tx.execute_batch(
-            r#"
-            PRAGMA user_version = 1;
-            
-            CREATE TABLE IF NOT EXISTS documents (
-                id TEXT PRIMARY KEY,
-                data BLOB NOT NULL,
-                metadata TEXT NOT NULL,
-                created_at INTEGER NOT NULL,
-                updated_at INTEGER NOT NULL
-            );
-            
-            CREATE INDEX IF NOT EXISTS idx_documents_created_at ON documents(created_at);
-            CREATE INDEX IF NOT EXISTS idx_documents_updated_at ON documents(updated_at);
-            "#,
-        )?;
-        
-        tx.commit()?;
-        Ok(())
+        migrations::migrate(&mut conn)?;
+        migrations::has_full_text_index(&conn)
+    }
+    
+    pub fn has_full_text(&self) -> bool {
+        self.full_text
+    }
+    
+    pub fn schema_version(&self) -> SqliteResult<u32> {
+        let conn = self.conn.lock().unwrap();
+        migrations::schema_version(&conn)
     }
     
     pub fn store_document(&self, id: &str, data: &[u8], metadata: &str) -> SqliteResult<()> {
         let conn = self.conn.lock().unwrap();
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as i64;
-        
-        conn.execute(
-            r#"
-            INSERT INTO documents (id, data, metadata, created_at, updated_at)
-            VALUES (?1, ?2, ?3, ?4, ?5)
-            ON CONFLICT(id) DO UPDATE SET
-                data = excluded.data,
-                metadata = excluded.metadata,
-                updated_at = excluded.updated_at
-            "#,
-            params![id, data, metadata, now, now],
-        )?;
-        
-        Ok(())
+        upsert_document(&conn, self.full_text, id, data, metadata)
     }
     
     pub fn get_document(&self, id: &str) -> SqliteResult<Option<StoredDocument>> {
@@ -107,6 +114,19 @@ impl Storage {
         }
     }
     
+    pub fn update_metadata(&self, id: &str, metadata: &str) -> SqliteResult<bool> {
+        let conn = self.conn.lock().unwrap();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let count = conn.execute(
+            "UPDATE documents SET metadata = ?2, updated_at = ?3 WHERE id = ?1",
+            params![id, metadata, now],
+        )?;
+        Ok(count > 0)
+    }
+    
     pub fn delete_document(&self, id: &str) -> SqliteResult<bool> {
         let conn = self.conn.lock().unwrap();
         let count = conn.execute("DELETE FROM documents WHERE id = ?", params![id])?;
@@ -144,23 +164,134 @@ impl Storage {
         Ok(documents)
     }
     
+    pub fn document_count(&self) -> SqliteResult<usize> {
+        let conn = self.conn.lock().unwrap();
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM documents", NO_PARAMS, |row| row.get(0))?;
+        Ok(count as usize)
+    }
+    
+    pub fn total_size(&self) -> SqliteResult<u64> {
+        let conn = self.conn.lock().unwrap();
+        let size: i64 = conn.query_row("SELECT COALESCE(SUM(size), 0) FROM documents", NO_PARAMS, |row| row.get(0))?;
+        Ok(size as u64)
+    }
+    
+    pub fn ensure_metadata_indexes(&self, paths: &[String]) -> SqliteResult<()> {
+        let conn = self.conn.lock().unwrap();
+        let columns = metadata::ensure_indexes(&conn, paths)?;
+        *self.metadata_columns.lock().unwrap() = columns;
+        Ok(())
+    }
+    
+    pub fn query_metadata(
+        &self,
+        queries: &[MetadataQuery],
+        limit: Option<i64>,
+    ) -> SqliteResult<Vec<StoredDocument>> {
+        let (clause, mut values) = {
+            let columns = self.metadata_columns.lock().unwrap();
+            metadata::where_clause(queries, &columns)?
+        };
+        values.push(limit.unwrap_or(100).into());
+        
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, data, metadata, created_at, updated_at FROM documents 
+             WHERE {} 
+             ORDER BY id 
+             LIMIT ?{}",
+            clause,
+            values.len()
+        ))?;
+        
+        let rows = stmt.query_map(values, |row| {
+            Ok(StoredDocument {
+                id: row.get(0)?,
+                data: row.get(1)?,
+                metadata: row.get(2)?,
+                created_at: row.get(3)?,
+                updated_at: row.get(4)?,
+            })
+        })?;
+        
+        let mut documents = Vec::new();
+        for row in rows {
+            documents.push(row?);
+        }
+        
+        Ok(documents)
+    }
+    
     pub fn search_documents(
         &self,
         query: &str,
         limit: Option<i64>,
     ) -> SqliteResult<Vec<StoredDocument>> {
-        let conn = self.conn.lock().unwrap();
+        let matches = self.search_ranked(query, limit)?;
+        Ok(matches.into_iter().map(|m| m.document).collect())
+    }
+    
+    pub fn search_ranked(&self, query: &str, limit: Option<i64>) -> SqliteResult<Vec<DocumentMatch>> {
         let limit = limit.unwrap_or(100);
-        let query = format!("%{}%", query);
+        if !self.full_text {
+            return self.search_fallback(query, limit);
+        }
+        
+        let match_query = fts_query(query);
+        if match_query.is_empty() {
+            return Ok(Vec::new());
+        }
+        
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT d.id, d.data, d.metadata, d.created_at, d.updated_at,
+                    bm25(documents_fts) AS rank,
+                    snippet(documents_fts, 1, ?2, ?3, ?4, ?5)
+             FROM documents_fts
+             JOIN documents d ON d.rowid = documents_fts.rowid
+             WHERE documents_fts MATCH ?1
+             ORDER BY rank
+             LIMIT ?6",
+        )?;
+        
+        let rows = stmt.query_map(
+            params![match_query, SNIPPET_START, SNIPPET_END, SNIPPET_ELLIPSIS, SNIPPET_TOKENS, limit],
+            |row| {
+                Ok(DocumentMatch {
+                    document: StoredDocument {
+                        id: row.get(0)?,
+                        data: row.get(1)?,
+                        metadata: row.get(2)?,
+                        created_at: row.get(3)?,
+                        updated_at: row.get(4)?,
+                    },
+                    score: -row.get::<_, f64>(5)?,
+                    snippet: row.get::<_, Option<String>>(6)?.unwrap_or_default(),
+                })
+            },
+        )?;
+        
+        let mut matches = Vec::new();
+        for row in rows {
+            matches.push(row?);
+        }
+        
+        Ok(matches)
+    }
+    
+    fn search_fallback(&self, query: &str, limit: i64) -> SqliteResult<Vec<DocumentMatch>> {
+        let conn = self.conn.lock().unwrap();
+        let pattern = like_pattern(query);
         
         let mut stmt = conn.prepare(
             "SELECT id, data, metadata, created_at, updated_at FROM documents 
-             WHERE id LIKE ?1 OR metadata LIKE ?1 
+             WHERE id LIKE ?1 ESCAPE '\\' OR metadata LIKE ?1 ESCAPE '\\' 
+                OR CAST(data AS TEXT) LIKE ?1 ESCAPE '\\' 
              ORDER BY updated_at DESC 
              LIMIT ?2",
         )?;
         
-        let rows = stmt.query_map(params![query, limit], |row| {
+        let rows = stmt.query_map(params![pattern, limit], |row| {
             Ok(StoredDocument {
                 id: row.get(0)?,
                 data: row.get(1)?,
@@ -170,12 +301,18 @@ impl Storage {
             })
         })?;
         
-        let mut documents = Vec::new();
+        let mut matches = Vec::new();
         for row in rows {
-            documents.push(row?);
+            let document = row?;
+            let snippet = fallback_snippet(&document_text(&document.id, &document.data), query);
+            matches.push(DocumentMatch {
+                document,
+                score: 0.0,
+                snippet,
+            });
         }
         
-        Ok(documents)
+        Ok(matches)
     }
 }
 
@@ -184,10 +321,108 @@ impl Clone for Storage {
         Storage {
             conn: self.conn.clone(),
             path: self.path.clone(),
+            full_text: self.full_text,
+            metadata_columns: self.metadata_columns.clone(),
         }
     }
 }
 
+fn upsert_document(
+    conn: &Connection,
+    full_text: bool,
+    id: &str,
+    data: &[u8],
+    metadata: &str,
+) -> SqliteResult<()> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    
+    if full_text {
+        conn.prepare_cached(
+            r#"
+            INSERT INTO documents (id, data, metadata, size, created_at, updated_at, text)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            ON CONFLICT(id) DO UPDATE SET
+                data = excluded.data,
+                metadata = excluded.metadata,
+                size = excluded.size,
+                updated_at = excluded.updated_at,
+                text = excluded.text
+            "#,
+        )?
+        .execute(params![id, data, metadata, data.len() as i64, now, now, document_text(id, data)])?;
+        return Ok(());
+    }
+    
+    conn.prepare_cached(
+        r#"
+        INSERT INTO documents (id, data, metadata, size, created_at, updated_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+        ON CONFLICT(id) DO UPDATE SET
+            data = excluded.data,
+            metadata = excluded.metadata,
+            size = excluded.size,
+            updated_at = excluded.updated_at
+        "#,
+    )?
+    .execute(params![id, data, metadata, data.len() as i64, now, now])?;
+    
+    Ok(())
+}
+
+fn document_text(id: &str, data: &[u8]) -> String {
+    EXTRACTORS.extract_text(id, data).unwrap_or_default()
+}
+
+fn like_pattern(query: &str) -> String {
+    let mut pattern = String::with_capacity(query.len() + 2);
+    pattern.push('%');
+    for c in query.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
+
+fn fts_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn fallback_snippet(text: &str, query: &str) -> String {
+    let lower = text.to_lowercase();
+    let needle = query.to_lowercase();
+    let Some((start, end)) = lower
+        .find(&needle)
+        .map(|start| (start, start + needle.len()))
+        .filter(|&(start, end)| {
+            lower.len() == text.len() && text.is_char_boundary(start) && text.is_char_boundary(end)
+        })
+    else {
+        return text.chars().take(FALLBACK_SNIPPET_CHARS).collect();
+    };
+    let before: String = text[..start].chars().rev().take(FALLBACK_SNIPPET_CHARS / 2).collect();
+    let after: String = text[end..].chars().take(FALLBACK_SNIPPET_CHARS / 2).collect();
+    format!(
+        "{}{}{}{}{}{}{}",
+        if before.len() < start { SNIPPET_ELLIPSIS } else { "" },
+        before.chars().rev().collect::<String>(),
+        SNIPPET_START,
+        &text[start..end],
+        SNIPPET_END,
+        after,
+        if end + after.len() < text.len() { SNIPPET_ELLIPSIS } else { "" },
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -196,7 +431,7 @@ mod tests {
     
     #[test]
     fn test_storage_operations() -> SqliteResult<()> {
-        let temp_dir = tempdir()?;
+        let temp_dir = tempdir().unwrap();
         let db_path = temp_dir.path().join("test.db");
         let storage = Storage::new(&db_path)?;
         
@@ -211,13 +446,23 @@ mod tests {
         assert_eq!(doc.data, data);
         assert_eq!(doc.metadata, metadata);
         
+        assert!(storage.update_metadata(id, r#"{"key":"changed"}"#)?);
+        assert!(!storage.update_metadata("missing", "{}")?);
+        let doc = storage.get_document(id)?.unwrap();
+        assert_eq!((doc.data.as_slice(), doc.metadata.as_str()), (&data[..], r#"{"key":"changed"}"#));
+        
         let docs = storage.list_documents(Some(10), Some(0))?;
         assert!(!docs.is_empty());
         
         let search_results = storage.search_documents("test", Some(10))?;
         assert!(!search_results.is_empty());
         
+        let scanned = storage.scan_documents(10).map(|doc| doc.map(|doc| doc.id)).collect::<SqliteResult<Vec<_>>>()?;
+        assert_eq!(scanned, vec![id.to_string()]);
+        assert_eq!(storage.document_count()?, 1);
+        
         let deleted = storage.delete_document(id)?;
+        assert!(storage.search_documents("test", Some(10))?.is_empty());
         assert!(deleted);
         
         let doc = storage.get_document(id)?;
@@ -225,4 +470,77 @@ mod tests {
         
         Ok(())
     }
+    
+    #[test]
+    fn test_full_text_search() -> SqliteResult<()> {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("fts.db");
+        let storage = Storage::new(&db_path)?;
+        assert!(storage.has_full_text());
+        
+        storage.store_document("report.txt", b"quarterly budget report for the finance team", "{}")?;
+        storage.store_document("budget.txt", b"budget budget budget", "{}")?;
+        storage.store_document("notes.txt", b"meeting notes", r#"{"topic":"budget"}"#)?;
+        storage.store_document("notes.txt", b"meeting notes", "{}")?;
+        let utf16: Vec<u8> = [0xFF, 0xFE]
+            .into_iter()
+            .chain("forecast for the budget".encode_utf16().flat_map(u16::to_le_bytes))
+            .collect();
+        storage.store_document("forecast.txt", &utf16, "{}")?;
+        
+        let forecast = storage.search_ranked("forecast", Some(10))?;
+        assert_eq!(forecast[0].document.id, "forecast.txt");
+        assert_eq!(forecast[0].snippet, "<b>forecast</b> for the budget");
+        assert!(storage.delete_document("forecast.txt")?);
+        assert!(storage.search_ranked("forecast", Some(10))?.is_empty());
+        
+        let matches = storage.search_ranked("budget", Some(10))?;
+        let ids: Vec<&str> = matches.iter().map(|m| m.document.id.as_str()).collect();
+        assert_eq!(ids, vec!["budget.txt", "report.txt"]);
+        assert!(matches[0].score > matches[1].score);
+        assert_eq!(matches[1].snippet, "quarterly <b>budget</b> report for the finance team");
+        assert!(storage.search_ranked("\"unbalanced -quote", Some(10))?.is_empty());
+        
+        drop(storage);
+        Connection::open(&db_path)?.execute_batch(
+            "DROP TRIGGER documents_fts_insert;
+             DROP TRIGGER documents_fts_delete;
+             DROP TRIGGER documents_fts_update;
+             DROP TABLE documents_fts;",
+        )?;
+        
+        let storage = Storage::new(&db_path)?;
+        assert!(!storage.has_full_text());
+        let matches = storage.search_ranked("FINANCE", Some(10))?;
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].snippet, "...y budget report for the <b>finance</b> team");
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_fallback_escapes_like_wildcards() -> SqliteResult<()> {
+        let temp_dir = tempdir().unwrap();
+        let storage = Storage::new(temp_dir.path().join("like.db"))?;
+        storage.store_document("discount.txt", b"save 50% today", "{}")?;
+        storage.store_document("plain.txt", b"save 500 today", "{}")?;
+        storage.store_document("path.txt", br"C:\temp\a_b", "{}")?;
+        storage.store_document("axb.txt", b"a-b", "{}")?;
+        
+        let ids = |query: &str| -> SqliteResult<Vec<String>> {
+            Ok(storage.search_fallback(query, 10)?.into_iter().map(|m| m.document.id).collect())
+        };
+        assert_eq!(ids("50%")?, vec!["discount.txt"]);
+        assert_eq!(ids("a_b")?, vec!["path.txt"]);
+        assert_eq!(ids(r"\temp")?, vec!["path.txt"]);
+        assert_eq!(like_pattern(r"5%_\"), r"%5\%\_\\%");
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_fallback_snippet_uses_needle_length() {
+        assert_eq!(fallback_snippet("i\u{307}stanbul trip", "\u{130}STANBUL"), "<b>i\u{307}stanbul</b> trip");
+        assert_eq!(fallback_snippet("\u{130}stanbul", "stanbul"), "\u{130}stanbul");
+    }
 }
@@ -0,0 +1,179 @@
+use std::collections::VecDeque;
+use std::sync::MutexGuard;
+
+use rusqlite::{params, Connection, Result as SqliteResult};
+
+use super::{upsert_document, Storage, StoredDocument};
+
+pub struct StorageTransaction<'a> {
+    conn: MutexGuard<'a, Connection>,
+    full_text: bool,
+    finished: bool,
+}
+
+impl StorageTransaction<'_> {
+    pub fn store_document(&self, id: &str, data: &[u8], metadata: &str) -> SqliteResult<()> {
+        upsert_document(&self.conn, self.full_text, id, data, metadata)
+    }
+
+    pub fn delete_document(&self, id: &str) -> SqliteResult<bool> {
+        let count = self
+            .conn
+            .prepare_cached("DELETE FROM documents WHERE id = ?")?
+            .execute(params![id])?;
+        Ok(count > 0)
+    }
+
+    pub fn commit(mut self) -> SqliteResult<()> {
+        self.finished = true;
+        self.conn.execute_batch("COMMIT")
+    }
+
+    pub fn rollback(mut self) -> SqliteResult<()> {
+        self.finished = true;
+        self.conn.execute_batch("ROLLBACK")
+    }
+}
+
+impl Drop for StorageTransaction<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = self.conn.execute_batch("ROLLBACK");
+        }
+    }
+}
+
+pub struct DocumentCursor<'a> {
+    storage: &'a Storage,
+    batch_size: usize,
+    last_rowid: i64,
+    buffer: VecDeque<StoredDocument>,
+    exhausted: bool,
+}
+
+impl DocumentCursor<'_> {
+    fn fetch(&mut self) -> SqliteResult<()> {
+        let conn = self.storage.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT rowid, id, data, metadata, created_at, updated_at FROM documents
+             WHERE rowid > ?1 ORDER BY rowid LIMIT ?2",
+        )?;
+
+        let rows = stmt.query_map(params![self.last_rowid, self.batch_size as i64], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                StoredDocument {
+                    id: row.get(1)?,
+                    data: row.get(2)?,
+                    metadata: row.get(3)?,
+                    created_at: row.get(4)?,
+                    updated_at: row.get(5)?,
+                },
+            ))
+        })?;
+
+        for row in rows {
+            let (rowid, document) = row?;
+            self.last_rowid = rowid;
+            self.buffer.push_back(document);
+        }
+        self.exhausted = self.buffer.len() < self.batch_size;
+        Ok(())
+    }
+}
+
+impl Iterator for DocumentCursor<'_> {
+    type Item = SqliteResult<StoredDocument>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buffer.is_empty() && !self.exhausted {
+            if let Err(e) = self.fetch() {
+                self.exhausted = true;
+                return Some(Err(e));
+            }
+        }
+        self.buffer.pop_front().map(Ok)
+    }
+}
+
+impl Storage {
+    pub fn transaction(&self) -> SqliteResult<StorageTransaction<'_>> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute_batch("BEGIN IMMEDIATE")?;
+        Ok(StorageTransaction {
+            conn,
+            full_text: self.full_text,
+            finished: false,
+        })
+    }
+
+    pub fn store_batch<'d, I>(&self, documents: I) -> SqliteResult<usize>
+    where
+        I: IntoIterator<Item = (&'d str, &'d [u8], &'d str)>,
+    {
+        let tx = self.transaction()?;
+        let mut count = 0;
+        for (id, data, metadata) in documents {
+            tx.store_document(id, data, metadata)?;
+            count += 1;
+        }
+        tx.commit()?;
+        Ok(count)
+    }
+
+    pub fn scan_documents(&self, batch_size: usize) -> DocumentCursor<'_> {
+        DocumentCursor {
+            storage: self,
+            batch_size: batch_size.max(1),
+            last_rowid: 0,
+            buffer: VecDeque::new(),
+            exhausted: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_batches_transactions_and_cursor() -> SqliteResult<()> {
+        let temp_dir = tempdir().unwrap();
+        let storage = Storage::new(temp_dir.path().join("batch.db"))?;
+
+        let bodies: Vec<(String, Vec<u8>)> = (0..25)
+            .map(|i| (format!("doc-{:02}", i), format!("body {}", i).into_bytes()))
+            .collect();
+        let stored = storage.store_batch(bodies.iter().map(|(id, data)| (id.as_str(), data.as_slice(), "{}")))?;
+        assert_eq!(stored, 25);
+
+        {
+            let tx = storage.transaction()?;
+            tx.store_document("rolled-back", b"never visible", "{}")?;
+            assert!(tx.delete_document("doc-00")?);
+        }
+        let tx = storage.transaction()?;
+        tx.store_document("doc-00", b"rewritten", "{}")?;
+        tx.rollback()?;
+        assert!(storage.get_document("rolled-back")?.is_none());
+        assert_eq!(storage.get_document("doc-00")?.unwrap().data, b"body 0");
+
+        let tx = storage.transaction()?;
+        assert!(tx.delete_document("doc-24")?);
+        tx.commit()?;
+
+        let mut cursor = storage.scan_documents(10);
+        let first = cursor.next().unwrap()?;
+        assert_eq!(first.id, "doc-00");
+        assert_eq!(cursor.buffer.len(), 9);
+        storage.store_document("late", b"appended while scanning", "{}")?;
+
+        let rest: Vec<String> = cursor.map(|doc| doc.map(|doc| doc.id)).collect::<SqliteResult<_>>()?;
+        assert_eq!(rest.len(), 24);
+        assert_eq!(rest.first().map(String::as_str), Some("doc-01"));
+        assert_eq!(rest.last().map(String::as_str), Some("late"));
+        assert_eq!(storage.scan_documents(0).count(), 25);
+        Ok(())
+    }
+}
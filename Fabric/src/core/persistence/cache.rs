@@ -0,0 +1,247 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use rusqlite::Result as SqliteResult;
+
+use super::Storage;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub resident_bytes: usize,
+    pub capacity_bytes: usize,
+    pub evictions: u64,
+}
+
+pub struct LruCache {
+    capacity: usize,
+    used: usize,
+    tick: u64,
+    evictions: u64,
+    entries: HashMap<String, (Arc<Vec<u8>>, u64)>,
+    recency: BTreeMap<u64, String>,
+}
+
+impl LruCache {
+    pub fn new(capacity_bytes: usize) -> Self {
+        LruCache {
+            capacity: capacity_bytes,
+            used: 0,
+            tick: 0,
+            evictions: 0,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+        }
+    }
+
+    pub fn get(&mut self, key: &str) -> Option<Arc<Vec<u8>>> {
+        self.tick += 1;
+        let (data, last_used) = self.entries.get_mut(key)?;
+        self.recency.remove(last_used);
+        *last_used = self.tick;
+        self.recency.insert(self.tick, key.to_string());
+        Some(data.clone())
+    }
+
+    pub fn insert(&mut self, key: &str, data: Arc<Vec<u8>>) {
+        self.remove(key);
+        let size = entry_size(key, &data);
+        if size > self.capacity {
+            return;
+        }
+        while self.used + size > self.capacity {
+            let Some((_, oldest)) = self.recency.pop_first() else {
+                break;
+            };
+            if let Some((evicted, _)) = self.entries.remove(&oldest) {
+                self.used -= entry_size(&oldest, &evicted);
+                self.evictions += 1;
+            }
+        }
+
+        self.tick += 1;
+        self.used += size;
+        self.recency.insert(self.tick, key.to_string());
+        self.entries.insert(key.to_string(), (data, self.tick));
+    }
+
+    pub fn remove(&mut self, key: &str) -> bool {
+        match self.entries.remove(key) {
+            Some((data, last_used)) => {
+                self.recency.remove(&last_used);
+                self.used -= entry_size(key, &data);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.entries.len(),
+            resident_bytes: self.used,
+            capacity_bytes: self.capacity,
+            evictions: self.evictions,
+        }
+    }
+}
+
+fn entry_size(key: &str, data: &[u8]) -> usize {
+    key.len() + data.len()
+}
+
+struct CacheState {
+    entries: LruCache,
+    generation: u64,
+}
+
+pub struct CachedStorage {
+    storage: Storage,
+    cache: Mutex<CacheState>,
+}
+
+impl CachedStorage {
+    pub fn new(storage: Storage, capacity_bytes: usize) -> Self {
+        CachedStorage {
+            storage,
+            cache: Mutex::new(CacheState {
+                entries: LruCache::new(capacity_bytes),
+                generation: 0,
+            }),
+        }
+    }
+
+    pub fn storage(&self) -> &Storage {
+        &self.storage
+    }
+
+    pub fn get(&self, id: &str) -> SqliteResult<(Option<Arc<Vec<u8>>>, bool)> {
+        let generation = match self.cached(id) {
+            Ok(data) => return Ok((Some(data), true)),
+            Err(generation) => generation,
+        };
+        let data = self.storage.get_document(id)?.map(|document| Arc::new(document.data));
+        if let Some(data) = &data {
+            self.fill(id, data.clone(), generation);
+        }
+        Ok((data, false))
+    }
+
+    fn cached(&self, id: &str) -> Result<Arc<Vec<u8>>, u64> {
+        let mut cache = self.cache.lock();
+        cache.entries.get(id).ok_or(cache.generation)
+    }
+
+    fn fill(&self, id: &str, data: Arc<Vec<u8>>, generation: u64) -> bool {
+        let mut cache = self.cache.lock();
+        if cache.generation != generation {
+            return false;
+        }
+        cache.entries.insert(id, data);
+        true
+    }
+
+    fn invalidate<F: FnOnce(&mut LruCache)>(&self, update: F) {
+        let mut cache = self.cache.lock();
+        update(&mut cache.entries);
+        cache.generation += 1;
+    }
+
+    pub fn store_document(&self, id: &str, data: &[u8], metadata: &str) -> SqliteResult<()> {
+        self.storage.store_document(id, data, metadata)?;
+        self.invalidate(|cache| cache.insert(id, Arc::new(data.to_vec())));
+        Ok(())
+    }
+
+    pub fn update_metadata(&self, id: &str, metadata: &str) -> SqliteResult<bool> {
+        self.storage.update_metadata(id, metadata)
+    }
+
+    pub fn store_batch<'d, I>(&self, documents: I) -> SqliteResult<usize>
+    where
+        I: IntoIterator<Item = (&'d str, &'d [u8], &'d str)>,
+    {
+        let documents: Vec<_> = documents.into_iter().collect();
+        let count = self.storage.store_batch(documents.iter().copied())?;
+        self.invalidate(|cache| {
+            for (id, data, _) in documents {
+                cache.insert(id, Arc::new(data.to_vec()));
+            }
+        });
+        Ok(count)
+    }
+
+    pub fn delete_document(&self, id: &str) -> SqliteResult<bool> {
+        let deleted = self.storage.delete_document(id)?;
+        self.invalidate(|cache| {
+            cache.remove(id);
+        });
+        Ok(deleted)
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.cache.lock().entries.stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_lru_eviction_by_bytes() {
+        let mut cache = LruCache::new(30);
+        cache.insert("a", Arc::new(vec![0; 9]));
+        cache.insert("b", Arc::new(vec![0; 9]));
+        cache.insert("c", Arc::new(vec![0; 9]));
+        assert!(cache.get("a").is_some());
+
+        cache.insert("d", Arc::new(vec![0; 9]));
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("a").is_some() && cache.get("c").is_some() && cache.get("d").is_some());
+        assert_eq!(cache.stats(), CacheStats { entries: 3, resident_bytes: 30, capacity_bytes: 30, evictions: 1 });
+
+        cache.insert("huge", Arc::new(vec![0; 64]));
+        assert!(cache.get("huge").is_none());
+        assert!(cache.remove("a"));
+        assert_eq!(cache.stats().resident_bytes, 20);
+    }
+
+    #[test]
+    fn test_cached_storage_reads_through() {
+        let temp_dir = tempdir().unwrap();
+        let storage = Storage::new(temp_dir.path().join("cache.db")).unwrap();
+        storage.store_document("cold", b"on disk", "{}").unwrap();
+
+        let cached = CachedStorage::new(storage, 1024);
+        assert_eq!(cached.get("cold").unwrap(), (Some(Arc::new(b"on disk".to_vec())), false));
+        assert!(cached.get("cold").unwrap().1);
+
+        cached.store_document("hot", b"written", "{}").unwrap();
+        assert!(cached.get("hot").unwrap().1);
+        assert!(cached.delete_document("hot").unwrap());
+        assert_eq!(cached.get("hot").unwrap(), (None, false));
+        assert_eq!(cached.stats().entries, 1);
+    }
+
+    #[test]
+    fn test_stale_read_is_not_cached() {
+        let temp_dir = tempdir().unwrap();
+        let storage = Storage::new(temp_dir.path().join("race.db")).unwrap();
+        storage.store_document("doc", b"old", "{}").unwrap();
+        let cached = CachedStorage::new(storage, 1024);
+
+        let generation = cached.cached("doc").unwrap_err();
+        let stale = Arc::new(cached.storage().get_document("doc").unwrap().unwrap().data);
+        cached.store_document("doc", b"new", "{}").unwrap();
+        assert!(!cached.fill("doc", stale.clone(), generation));
+        assert_eq!(cached.get("doc").unwrap(), (Some(Arc::new(b"new".to_vec())), true));
+
+        let generation = cached.cached("other").unwrap_err();
+        assert!(cached.delete_document("doc").unwrap());
+        assert!(!cached.fill("doc", stale, generation));
+        assert_eq!(cached.get("doc").unwrap(), (None, false));
+    }
+}
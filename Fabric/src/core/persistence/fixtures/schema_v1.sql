@@ -0,0 +1,16 @@
+CREATE TABLE documents (
+    id TEXT PRIMARY KEY,
+    data BLOB NOT NULL,
+    metadata TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+CREATE INDEX idx_documents_created_at ON documents(created_at);
+CREATE INDEX idx_documents_updated_at ON documents(updated_at);
+
+INSERT INTO documents (id, data, metadata, created_at, updated_at) VALUES
+    ('notes.txt', X'6D656574696E67206E6F746573', '{"author":"ann"}', 1600000000, 1600000000),
+    ('todo.txt', X'627579206D696C6B', '{}', 1600000100, 1600000200);
+
+PRAGMA user_version = 1;
@@ -0,0 +1,253 @@
+use rusqlite::types::Value;
+use rusqlite::{ffi, Connection, Error, Result as SqliteResult, NO_PARAMS};
+
+const COLUMN_PREFIX: &str = "meta_";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetadataQuery {
+    Equals { path: String, value: Value },
+    Range { path: String, min: Option<Value>, max: Option<Value> },
+    Contains { path: String, value: String },
+}
+
+impl MetadataQuery {
+    pub fn equals(path: &str, value: impl Into<Value>) -> Self {
+        MetadataQuery::Equals {
+            path: path.to_string(),
+            value: value.into(),
+        }
+    }
+
+    pub fn range<V: Into<Value>>(path: &str, min: Option<V>, max: Option<V>) -> Self {
+        MetadataQuery::Range {
+            path: path.to_string(),
+            min: min.map(Into::into),
+            max: max.map(Into::into),
+        }
+    }
+
+    pub fn contains(path: &str, value: &str) -> Self {
+        MetadataQuery::Contains {
+            path: path.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    fn path(&self) -> &str {
+        match self {
+            MetadataQuery::Equals { path, .. }
+            | MetadataQuery::Range { path, .. }
+            | MetadataQuery::Contains { path, .. } => path,
+        }
+    }
+}
+
+pub fn json_path(path: &str) -> SqliteResult<String> {
+    let path = if path.starts_with('$') {
+        path.to_string()
+    } else {
+        format!("$.{}", path)
+    };
+    let valid = path.len() > 2
+        && path
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '$' | '.' | '_' | '-' | '[' | ']'));
+    if !valid {
+        return Err(misuse(format!("Invalid metadata path: {}", path)));
+    }
+    Ok(path)
+}
+
+fn misuse(message: String) -> Error {
+    Error::SqliteFailure(ffi::Error::new(ffi::SQLITE_MISUSE), Some(message))
+}
+
+pub fn column_name(path: &str) -> String {
+    let name: String = path
+        .trim_start_matches('$')
+        .trim_start_matches('.')
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    format!("{}{}", COLUMN_PREFIX, name)
+}
+
+fn column_definition(column: &str, path: &str) -> String {
+    format!("{} GENERATED ALWAYS AS (json_extract(metadata, '{}')) VIRTUAL", column, path)
+}
+
+pub fn ensure_indexes(conn: &Connection, paths: &[String]) -> SqliteResult<Vec<(String, String)>> {
+    let mut columns: Vec<(String, String)> = Vec::new();
+    for path in paths {
+        let path = json_path(path)?;
+        let column = column_name(&path);
+        match columns.iter().find(|(_, existing)| *existing == column) {
+            Some((indexed, _)) if *indexed == path => continue,
+            Some((indexed, _)) => {
+                return Err(misuse(format!(
+                    "Metadata paths {} and {} both map to column {}",
+                    indexed, path, column
+                )))
+            }
+            None => {}
+        }
+
+        let exists: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_xinfo('documents') WHERE name = ?1",
+            &[&column],
+            |row| row.get(0),
+        )?;
+        let definition = column_definition(&column, &path);
+        if exists {
+            let table: String = conn.query_row(
+                "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'documents'",
+                NO_PARAMS,
+                |row| row.get(0),
+            )?;
+            if !table.contains(&definition) {
+                return Err(misuse(format!(
+                    "Column {} already exists for a different metadata path than {}",
+                    column, path
+                )));
+            }
+        } else {
+            conn.execute_batch(&format!(
+                "ALTER TABLE documents ADD COLUMN {definition};
+                 CREATE INDEX IF NOT EXISTS idx_documents_{column} ON documents({column});",
+                definition = definition,
+                column = column,
+            ))?;
+        }
+        columns.push((path, column));
+    }
+    Ok(columns)
+}
+
+pub fn where_clause(
+    queries: &[MetadataQuery],
+    columns: &[(String, String)],
+) -> SqliteResult<(String, Vec<Value>)> {
+    let mut clauses = Vec::with_capacity(queries.len());
+    let mut params = Vec::new();
+
+    for query in queries {
+        let path = json_path(query.path())?;
+        let expr = match columns.iter().find(|(indexed, _)| *indexed == path) {
+            Some((_, column)) => column.clone(),
+            None => {
+                params.push(Value::Text(path.clone()));
+                format!("json_extract(metadata, ?{})", params.len())
+            }
+        };
+
+        let clause = match query {
+            MetadataQuery::Equals { value, .. } => {
+                params.push(value.clone());
+                format!("{} = ?{}", expr, params.len())
+            }
+            MetadataQuery::Range { min, max, .. } => {
+                let numeric = [min, max]
+                    .iter()
+                    .any(|bound| matches!(bound, Some(Value::Integer(_)) | Some(Value::Real(_))));
+                let (operand, mut parts) = if numeric {
+                    (
+                        format!("CAST({} AS REAL)", expr),
+                        vec![format!(
+                            "(typeof({e}) IN ('integer', 'real') OR (typeof({e}) = 'text' AND {e} <> '' AND trim({e}, '0123456789.-+eE') = ''))",
+                            e = expr
+                        )],
+                    )
+                } else {
+                    (expr.clone(), vec![format!("{} IS NOT NULL", expr)])
+                };
+                for (bound, op) in [(min, ">="), (max, "<=")] {
+                    if let Some(bound) = bound {
+                        params.push(bound.clone());
+                        parts.push(format!("{} {} ?{}", operand, op, params.len()));
+                    }
+                }
+                format!("({})", parts.join(" AND "))
+            }
+            MetadataQuery::Contains { value, .. } => {
+                params.push(Value::Text(path));
+                let path_param = params.len();
+                params.push(Value::Text(value.clone()));
+                let value_param = params.len();
+                format!(
+                    "(CASE json_type(metadata, ?{p})
+                        WHEN 'array' THEN EXISTS (SELECT 1 FROM json_each(metadata, ?{p}) WHERE value = ?{v})
+                        WHEN 'text' THEN instr({e}, ?{v}) > 0
+                        ELSE 0 END)",
+                    p = path_param,
+                    v = value_param,
+                    e = expr
+                )
+            }
+        };
+        clauses.push(clause);
+    }
+
+    if clauses.is_empty() {
+        clauses.push("1".to_string());
+    }
+    Ok((clauses.join(" AND "), params))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::Storage;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_metadata_queries() -> SqliteResult<()> {
+        let temp_dir = tempdir().unwrap();
+        let storage = Storage::new(temp_dir.path().join("meta.db"))?;
+        storage.ensure_metadata_indexes(&["author".to_string()])?;
+
+        storage.store_document("a", b"", r#"{"author":"ann","year":2019,"tags":["draft","q1"]}"#)?;
+        storage.store_document("b", b"", r#"{"author":"bob","year":"2021","tags":["final"]}"#)?;
+        storage.store_document("c", b"", r#"{"author":"annabel","year":2023,"title":"final notes"}"#)?;
+
+        let ids = |queries: &[MetadataQuery]| -> SqliteResult<Vec<String>> {
+            Ok(storage.query_metadata(queries, None)?.into_iter().map(|d| d.id).collect())
+        };
+        assert_eq!(ids(&[MetadataQuery::equals("author", "ann".to_string())])?, vec!["a"]);
+        assert_eq!(ids(&[MetadataQuery::range("year", Some(2020), None)])?, vec!["b", "c"]);
+        assert_eq!(ids(&[MetadataQuery::range("author", Some("ann".to_string()), Some("anz".to_string()))])?, vec!["a", "c"]);
+        assert_eq!(ids(&[MetadataQuery::contains("tags", "final")])?, vec!["b"]);
+        assert_eq!(ids(&[MetadataQuery::contains("$.title", "notes")])?, vec!["c"]);
+        assert_eq!(
+            ids(&[MetadataQuery::contains("author", "ann"), MetadataQuery::range("year", Some(2020), Some(2030))])?,
+            vec!["c"]
+        );
+        assert!(storage.query_metadata(&[MetadataQuery::equals("x'); DROP TABLE documents; --", 1)], None).is_err());
+
+        let conn = Connection::open(temp_dir.path().join("meta.db"))?;
+        let plan: String = conn.query_row(
+            "EXPLAIN QUERY PLAN SELECT id FROM documents WHERE meta_author = 'ann'",
+            NO_PARAMS,
+            |row| row.get(3),
+        )?;
+        assert!(plan.contains("idx_documents_meta_author"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_index_paths_map_to_distinct_columns() -> SqliteResult<()> {
+        let temp_dir = tempdir().unwrap();
+        let storage = Storage::new(temp_dir.path().join("columns.db"))?;
+        storage.ensure_metadata_indexes(&["a_b".to_string(), "$.a_b".to_string()])?;
+        storage.ensure_metadata_indexes(&["a_b".to_string()])?;
+
+        let error = storage.ensure_metadata_indexes(&["a.b".to_string()]).unwrap_err();
+        assert!(error.to_string().contains("different metadata path"));
+        let error = storage.ensure_metadata_indexes(&["x.y".to_string(), "x_y".to_string()]).unwrap_err();
+        assert!(error.to_string().contains("both map to column meta_x_y"));
+
+        storage.store_document("doc", b"", r#"{"a_b":1,"a":{"b":2}}"#)?;
+        let found = storage.query_metadata(&[MetadataQuery::equals("a_b", 1)], None)?;
+        assert_eq!(found.len(), 1);
+        Ok(())
+    }
+}
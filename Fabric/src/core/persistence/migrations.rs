@@ -0,0 +1,232 @@
+use rusqlite::{ffi, params, Connection, Error, Result as SqliteResult, Transaction, NO_PARAMS};
+
+use super::{document_text, CURRENT_SCHEMA_VERSION};
+
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    pub requires_full_text: bool,
+    pub apply: fn(&Transaction) -> SqliteResult<()>,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "create documents table",
+        requires_full_text: false,
+        apply: create_documents,
+    },
+    Migration {
+        version: 2,
+        description: "track document sizes",
+        requires_full_text: false,
+        apply: add_document_size,
+    },
+    Migration {
+        version: 3,
+        description: "full-text index over extracted document text",
+        requires_full_text: true,
+        apply: create_full_text_index,
+    },
+];
+
+pub const FULL_TEXT_TABLE: &str = "documents_fts";
+
+const BACKFILL_BATCH_SIZE: i64 = 256;
+
+fn create_documents(tx: &Transaction) -> SqliteResult<()> {
+    tx.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS documents (
+            id TEXT PRIMARY KEY,
+            data BLOB NOT NULL,
+            metadata TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_documents_created_at ON documents(created_at);
+        CREATE INDEX IF NOT EXISTS idx_documents_updated_at ON documents(updated_at);
+        "#,
+    )
+}
+
+fn add_document_size(tx: &Transaction) -> SqliteResult<()> {
+    tx.execute_batch(
+        r#"
+        ALTER TABLE documents ADD COLUMN size INTEGER NOT NULL DEFAULT 0;
+        UPDATE documents SET size = length(data);
+        "#,
+    )
+}
+
+fn create_full_text_index(tx: &Transaction) -> SqliteResult<()> {
+    tx.execute_batch("ALTER TABLE documents ADD COLUMN text TEXT NOT NULL DEFAULT '';")?;
+
+    let mut select = tx.prepare("SELECT rowid, id, data FROM documents WHERE rowid > ?1 ORDER BY rowid LIMIT ?2")?;
+    let mut update = tx.prepare("UPDATE documents SET text = ?2 WHERE rowid = ?1")?;
+    let mut last_rowid = 0;
+    loop {
+        let rows = select
+            .query_map(params![last_rowid, BACKFILL_BATCH_SIZE], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, Vec<u8>>(2)?))
+            })?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        for (rowid, id, data) in &rows {
+            update.execute(params![rowid, document_text(id, data)])?;
+        }
+        match rows.last() {
+            Some((rowid, _, _)) if rows.len() as i64 == BACKFILL_BATCH_SIZE => last_rowid = *rowid,
+            _ => break,
+        }
+    }
+
+    tx.execute_batch(
+        r#"
+        CREATE VIRTUAL TABLE documents_fts USING fts5(
+            id, text, metadata, content = 'documents', content_rowid = 'rowid'
+        );
+
+        INSERT INTO documents_fts (documents_fts) VALUES ('rebuild');
+
+        CREATE TRIGGER documents_fts_insert AFTER INSERT ON documents BEGIN
+            INSERT INTO documents_fts (rowid, id, text, metadata)
+                VALUES (new.rowid, new.id, new.text, new.metadata);
+        END;
+
+        CREATE TRIGGER documents_fts_delete AFTER DELETE ON documents BEGIN
+            INSERT INTO documents_fts (documents_fts, rowid, id, text, metadata)
+                VALUES ('delete', old.rowid, old.id, old.text, old.metadata);
+        END;
+
+        CREATE TRIGGER documents_fts_update AFTER UPDATE ON documents BEGIN
+            INSERT INTO documents_fts (documents_fts, rowid, id, text, metadata)
+                VALUES ('delete', old.rowid, old.id, old.text, old.metadata);
+            INSERT INTO documents_fts (rowid, id, text, metadata)
+                VALUES (new.rowid, new.id, new.text, new.metadata);
+        END;
+        "#,
+    )
+}
+
+fn full_text_available(conn: &Connection) -> SqliteResult<bool> {
+    conn.query_row(
+        "SELECT sqlite_compileoption_used('ENABLE_FTS5')",
+        NO_PARAMS,
+        |row| row.get(0),
+    )
+}
+
+pub fn has_full_text_index(conn: &Connection) -> SqliteResult<bool> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+        &[FULL_TEXT_TABLE],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+pub fn schema_version(conn: &Connection) -> SqliteResult<u32> {
+    conn.pragma_query_value(None, "user_version", |row| row.get(0))
+}
+
+pub fn migrate(conn: &mut Connection) -> SqliteResult<u32> {
+    let current = schema_version(conn)?;
+    if current > CURRENT_SCHEMA_VERSION {
+        return Err(Error::SqliteFailure(
+            ffi::Error::new(ffi::SQLITE_CANTOPEN),
+            Some(format!(
+                "Database schema version {} is newer than supported version {}",
+                current, CURRENT_SCHEMA_VERSION
+            )),
+        ));
+    }
+
+    let full_text = full_text_available(conn)?;
+    apply_migrations(conn, current, full_text)
+}
+
+fn apply_migrations(conn: &mut Connection, current: u32, full_text: bool) -> SqliteResult<u32> {
+    for migration in MIGRATIONS.iter().filter(|migration| migration.version > current) {
+        if migration.requires_full_text && !full_text {
+            break;
+        }
+        let tx = conn.transaction()?;
+        (migration.apply)(&tx)?;
+        tx.pragma_update(None, "user_version", &migration.version)?;
+        tx.commit()?;
+    }
+    schema_version(conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::Storage;
+    use tempfile::tempdir;
+
+    const V1_FIXTURE: &str = include_str!("fixtures/schema_v1.sql");
+
+    #[test]
+    fn test_migrations_are_ordered() {
+        let versions: Vec<u32> = MIGRATIONS.iter().map(|migration| migration.version).collect();
+        let expected: Vec<u32> = (1..=CURRENT_SCHEMA_VERSION).collect();
+        assert_eq!(versions, expected);
+        assert!(MIGRATIONS.iter().all(|migration| !migration.description.is_empty()));
+    }
+
+    #[test]
+    fn test_upgrade_v1_fixture() {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("v1.db");
+        Connection::open(&db_path).unwrap().execute_batch(V1_FIXTURE).unwrap();
+
+        let storage = Storage::new(&db_path).unwrap();
+        assert_eq!(storage.schema_version().unwrap(), CURRENT_SCHEMA_VERSION);
+
+        let notes = storage.get_document("notes.txt").unwrap().unwrap();
+        assert_eq!(notes.data, b"meeting notes");
+        assert_eq!(notes.metadata, r#"{"author":"ann"}"#);
+        assert_eq!(notes.created_at, 1600000000);
+        assert_eq!(storage.total_size().unwrap(), 21);
+        assert_eq!(storage.search_documents("milk", Some(10)).unwrap()[0].id, "todo.txt");
+
+        storage.store_document("notes.txt", b"notes", "{}").unwrap();
+        assert_eq!(storage.total_size().unwrap(), 13);
+        drop(storage);
+        assert_eq!(Storage::new(&db_path).unwrap().total_size().unwrap(), 13);
+    }
+
+    #[test]
+    fn test_refuses_newer_database() {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("future.db");
+        let conn = Connection::open(&db_path).unwrap();
+        conn.execute_batch(V1_FIXTURE).unwrap();
+        conn.pragma_update(None, "user_version", &(CURRENT_SCHEMA_VERSION + 1)).unwrap();
+        drop(conn);
+
+        let error = Storage::new(&db_path).unwrap_err();
+        assert!(error.to_string().contains("newer than supported"));
+
+        let conn = Connection::open(&db_path).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), CURRENT_SCHEMA_VERSION + 1);
+    }
+
+    #[test]
+    fn test_full_text_migrations_wait_for_fts5() {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("no-fts.db");
+        let mut conn = Connection::open(&db_path).unwrap();
+        conn.execute_batch(V1_FIXTURE).unwrap();
+
+        assert_eq!(apply_migrations(&mut conn, 1, false).unwrap(), 2);
+        assert!(!has_full_text_index(&conn).unwrap());
+        drop(conn);
+
+        let storage = Storage::new(&db_path).unwrap();
+        assert_eq!(storage.schema_version().unwrap(), CURRENT_SCHEMA_VERSION);
+        assert!(storage.has_full_text());
+        assert_eq!(storage.search_ranked("meeting", Some(10)).unwrap()[0].snippet, "<b>meeting</b> notes");
+    }
+}
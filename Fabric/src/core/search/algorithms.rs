@@ -1,75 +1,78 @@
-use std::cmp::min;
-use std::collections::HashMap;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
 
-const MAX_PREFIX: usize = 100;
 const MATCH_BONUS: i32 = 2;
 const CAMEL_BONUS: i32 = 2;
 const LEADING_LETTER_PENALTY: i32 = -3;
 const MAX_LEADING_LETTER_PENALTY: i32 = -9;
 const UNMATCHED_LETTER_PENALTY: i32 = -1;
 
+#[allow(dead_code)]
 extern "C" {
     fn fast_memcmp(ptr1: *const u8, ptr2: *const u8, len: usize) -> i32;
 }
 
 pub fn fuzzy_match(text: &str, pattern: &str) -> Option<f32> {
+    fuzzy_match_positions(text, pattern).map(|(score, _)| score)
+}
+
+pub fn fuzzy_match_positions(text: &str, pattern: &str) -> Option<(f32, Vec<usize>)> {
+    let pattern = fold_case(pattern);
     if pattern.is_empty() {
-        return Some(1.0);
+        return Some((1.0, Vec::new()));
     }
     
-    if text.is_empty() || pattern.len() > text.len() {
+    let units = fold_units(text);
+    let pattern: Vec<char> = pattern.chars().collect();
+    if units.is_empty() || pattern.len() > units.len() {
         return None;
     }
     
-    let text_bytes = text.as_bytes();
-    let pattern_bytes = pattern.as_bytes();
-    
     let mut score = 0i32;
     let mut pattern_idx = 0;
     let mut in_gap = false;
-    let mut last_char = 0 as char;
     let mut start = 0;
+    let mut positions: Vec<usize> = Vec::with_capacity(pattern.len());
     
-    for (i, &b) in text_bytes.iter().enumerate() {
-        let current_char = b as char;
-        
-        if pattern_idx < pattern_bytes.len() && b.eq_ignore_ascii_case(&pattern_bytes[pattern_idx]) {
+    for (i, unit) in units.iter().enumerate() {
+        if pattern_idx < pattern.len() && unit.folded == pattern[pattern_idx] {
             let mut char_score = 0;
+            if positions.last() != Some(&unit.char_index) {
+                positions.push(unit.char_index);
+            }
             
             if pattern_idx == 0 {
                 start = i;
-                char_score += (text_bytes.len() - i) as i32;
+                char_score += (units.len() - i) as i32;
             }
             
-            if i > 0 {
-                let prev_char = text_bytes[i - 1] as char;
+            if i > 0 && units[i - 1].grapheme != unit.grapheme {
+                let prev_char = units[i - 1].original;
+                let current_char = unit.original;
                 
                 if is_uppercase(current_char) && is_lowercase(prev_char) {
                     char_score += CAMEL_BONUS;
                 }
                 
-                if is_alphanumeric(prev_char) && !is_alphanumeric(current_char) {
+                if is_alphanumeric(prev_char) != is_alphanumeric(current_char) {
                     char_score += MATCH_BONUS;
-                } else if !is_alphanumeric(prev_char) && is_alphanumeric(current_char) {
-                    char_score += MATCH_BONUS;
-                }
-                
-                if in_gap {
-                    char_score += UNMATCHED_LETTER_PENALTY * (i - start - 1) as i32;
                 }
             }
             
+            if i > 0 && in_gap {
+                char_score += UNMATCHED_LETTER_PENALTY * (i - start).saturating_sub(1) as i32;
+            }
+            
             score += char_score;
             pattern_idx += 1;
             in_gap = false;
         } else {
             in_gap = true;
         }
-        
-        last_char = current_char;
     }
     
-    if pattern_idx != pattern_bytes.len() {
+    if pattern_idx != pattern.len() {
         return None;
     }
     
@@ -82,10 +85,50 @@ pub fn fuzzy_match(text: &str, pattern: &str) -> Option<f32> {
     
     score += penalty;
     
-    let max_score = (text_bytes.len() * MATCH_BONUS as usize) as i32;
-    let normalized = (score as f32 / max_score as f32).max(0.0).min(1.0);
+    let max_score = (units.len() * MATCH_BONUS as usize) as i32;
+    let normalized = (score as f32 / max_score as f32).clamp(0.0, 1.0);
     
-    Some(normalized)
+    Some((normalized, positions))
+}
+
+pub fn fold_case(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    for c in text.nfd() {
+        match c {
+            'ß' | 'ẞ' => folded.push_str("ss"),
+            'ς' => folded.push('σ'),
+            'ſ' => folded.push('s'),
+            _ => folded.extend(c.to_lowercase().filter(|l| !is_combining_mark(*l))),
+        }
+    }
+    folded
+}
+
+struct FoldedUnit {
+    folded: char,
+    original: char,
+    grapheme: usize,
+    char_index: usize,
+}
+
+fn fold_units(text: &str) -> Vec<FoldedUnit> {
+    let mut units = Vec::with_capacity(text.len());
+    let mut char_index = 0;
+    
+    for (grapheme, cluster) in text.graphemes(true).enumerate() {
+        let original = cluster.chars().next().unwrap_or_default();
+        for folded in fold_case(cluster).chars() {
+            units.push(FoldedUnit {
+                folded,
+                original,
+                grapheme,
+                char_index,
+            });
+        }
+        char_index += cluster.chars().count();
+    }
+    
+    units
 }
 
 pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
@@ -106,17 +149,17 @@ pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
 
 #[inline]
 fn is_uppercase(c: char) -> bool {
-    c.is_ascii_uppercase()
+    c.is_uppercase()
 }
 
 #[inline]
 fn is_lowercase(c: char) -> bool {
-    c.is_ascii_lowercase()
+    c.is_lowercase()
 }
 
 #[inline]
 fn is_alphanumeric(c: char) -> bool {
-    c.is_ascii_alphanumeric()
+    c.is_alphanumeric()
 }
 
 #[cfg(test)]
@@ -130,6 +173,40 @@ mod tests {
         assert!(fuzzy_match("HelloWorld", "HW").is_some());
     }
     
+    #[test]
+    fn test_fuzzy_match_unicode() {
+        assert!(fuzzy_match("Привет Мир.txt", "пм").is_some());
+        assert!(fuzzy_match("ПРИВЕТ", "привет").is_some());
+        assert!(fuzzy_match("Café Résumé.pdf", "resume").is_some());
+        assert!(fuzzy_match("Cafe\u{301} Re\u{301}sume\u{301}.pdf", "résumé").is_some());
+        assert!(fuzzy_match("Straße.docx", "strasse").is_some());
+        assert!(fuzzy_match("東京タワー.jpg", "東京").is_some());
+        assert!(fuzzy_match("東京タワー.jpg", "大阪").is_none());
+        assert!(fuzzy_match("日本", "日本語").is_none());
+        assert!(fuzzy_match("🇯🇵 trip", "trip").is_some());
+    }
+    
+    #[test]
+    fn test_fuzzy_match_unicode_ranking() {
+        let prefix = fuzzy_match("Ёлка.png", "елка").unwrap();
+        let buried = fuzzy_match("новогодняя ёлка.png", "елка").unwrap();
+        assert!(prefix > buried);
+    }
+    
+    #[test]
+    fn test_fuzzy_match_positions() {
+        let (_, positions) = fuzzy_match_positions("HelloWorld", "hw").unwrap();
+        assert_eq!(positions, vec![0, 5]);
+        
+        let (_, positions) = fuzzy_match_positions("Re\u{301}sume\u{301}.pdf", "rs").unwrap();
+        assert_eq!(positions, vec![0, 3]);
+        
+        let (_, positions) = fuzzy_match_positions("Straße", "asse").unwrap();
+        assert_eq!(positions, vec![3, 4, 5]);
+        
+        assert!(fuzzy_match_positions("hello", "hx").is_none());
+    }
+    
     #[test]
     fn test_cosine_similarity() {
         let a = vec![1.0, 2.0, 3.0];
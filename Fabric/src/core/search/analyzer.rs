@@ -0,0 +1,585 @@
+use std::collections::HashSet;
+
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::config::{AnalyzerConfig, Language, NGramConfig, TokenizerKind};
+
+pub trait Analyzer: Send + Sync {
+    fn analyze(&self, text: &str) -> Vec<String>;
+
+    fn normalize(&self, term: &str) -> String {
+        term.to_lowercase()
+    }
+}
+
+pub trait Tokenizer: Send + Sync {
+    fn tokenize(&self, text: &str) -> Vec<String>;
+}
+
+pub trait TokenFilter: Send + Sync {
+    fn filter(&self, tokens: Vec<String>) -> Vec<String>;
+
+    fn normalize(&self, term: String) -> String {
+        term
+    }
+}
+
+pub struct TextAnalyzer {
+    tokenizer: Box<dyn Tokenizer>,
+    filters: Vec<Box<dyn TokenFilter>>,
+}
+
+impl TextAnalyzer {
+    pub fn new(tokenizer: impl Tokenizer + 'static) -> Self {
+        TextAnalyzer {
+            tokenizer: Box::new(tokenizer),
+            filters: Vec::new(),
+        }
+    }
+
+    pub fn standard() -> Self {
+        Self::from_config(&AnalyzerConfig::default())
+    }
+
+    pub fn from_config(config: &AnalyzerConfig) -> Self {
+        let mut analyzer = match config.tokenizer {
+            TokenizerKind::Word => Self::new(WordTokenizer),
+            TokenizerKind::Whitespace => Self::new(WhitespaceTokenizer),
+        };
+        if config.lowercase {
+            analyzer = analyzer.filter(LowercaseFilter);
+        }
+        if config.ascii_folding {
+            analyzer = analyzer.filter(AsciiFoldingFilter);
+        }
+        if let Some(language) = config.stop_words {
+            analyzer = analyzer.filter(StopWordFilter::for_language(language));
+        }
+        if let Some(language) = config.stemmer {
+            analyzer = analyzer.filter(StemFilter::new(language));
+        }
+        if let Some(ngram) = config.ngram {
+            analyzer = analyzer.filter(NGramFilter::new(ngram));
+        }
+        analyzer
+    }
+
+    pub fn filter(mut self, filter: impl TokenFilter + 'static) -> Self {
+        self.filters.push(Box::new(filter));
+        self
+    }
+}
+
+impl Analyzer for TextAnalyzer {
+    fn analyze(&self, text: &str) -> Vec<String> {
+        let tokens = self.tokenizer.tokenize(text);
+        self.filters
+            .iter()
+            .fold(tokens, |tokens, filter| filter.filter(tokens))
+    }
+
+    fn normalize(&self, term: &str) -> String {
+        self.filters
+            .iter()
+            .fold(term.to_string(), |term, filter| filter.normalize(term))
+    }
+}
+
+pub struct WordTokenizer;
+
+impl Tokenizer for WordTokenizer {
+    fn tokenize(&self, text: &str) -> Vec<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|token| !token.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+pub struct WhitespaceTokenizer;
+
+impl Tokenizer for WhitespaceTokenizer {
+    fn tokenize(&self, text: &str) -> Vec<String> {
+        text.split_whitespace().map(str::to_string).collect()
+    }
+}
+
+pub struct LowercaseFilter;
+
+impl TokenFilter for LowercaseFilter {
+    fn filter(&self, tokens: Vec<String>) -> Vec<String> {
+        tokens.into_iter().map(|token| self.normalize(token)).collect()
+    }
+
+    fn normalize(&self, term: String) -> String {
+        term.to_lowercase()
+    }
+}
+
+pub struct AsciiFoldingFilter;
+
+impl TokenFilter for AsciiFoldingFilter {
+    fn filter(&self, tokens: Vec<String>) -> Vec<String> {
+        tokens.into_iter().map(|token| self.normalize(token)).collect()
+    }
+
+    fn normalize(&self, term: String) -> String {
+        if term.is_ascii() {
+            return term;
+        }
+
+        let mut folded = String::with_capacity(term.len());
+        for c in term.nfd() {
+            match c {
+                'ß' => folded.push_str("ss"),
+                'ẞ' => folded.push_str("SS"),
+                'æ' => folded.push_str("ae"),
+                'Æ' => folded.push_str("AE"),
+                'œ' => folded.push_str("oe"),
+                'Œ' => folded.push_str("OE"),
+                'ø' => folded.push('o'),
+                'Ø' => folded.push('O'),
+                'đ' | 'ð' => folded.push('d'),
+                'Đ' | 'Ð' => folded.push('D'),
+                'ł' => folded.push('l'),
+                'Ł' => folded.push('L'),
+                'þ' => folded.push_str("th"),
+                'Þ' => folded.push_str("TH"),
+                c if is_combining_mark(c) => {}
+                c => folded.push(c),
+            }
+        }
+        folded
+    }
+}
+
+pub struct StopWordFilter {
+    words: HashSet<String>,
+}
+
+impl StopWordFilter {
+    pub fn new<I, S>(words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        StopWordFilter {
+            words: words.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    pub fn for_language(language: Language) -> Self {
+        Self::new(stop_words(language).iter().copied())
+    }
+}
+
+impl TokenFilter for StopWordFilter {
+    fn filter(&self, tokens: Vec<String>) -> Vec<String> {
+        tokens
+            .into_iter()
+            .filter(|token| !self.words.contains(token))
+            .collect()
+    }
+}
+
+fn stop_words(language: Language) -> &'static [&'static str] {
+    match language {
+        Language::English => &[
+            "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "if", "in", "into", "is", "it",
+            "no", "not", "of", "on", "or", "such", "that", "the", "their", "then", "there", "these",
+            "they", "this", "to", "was", "will", "with",
+        ],
+        Language::German => &[
+            "aber", "als", "am", "an", "auch", "auf", "aus", "bei", "bin", "bis", "das", "dass", "dem",
+            "den", "der", "des", "die", "du", "ein", "eine", "einem", "einen", "einer", "es", "für",
+            "hat", "ich", "im", "in", "ist", "mit", "nicht", "oder", "sie", "sind", "und", "von", "war",
+            "wir", "zu", "zum", "zur",
+        ],
+        Language::French => &[
+            "au", "aux", "avec", "ce", "ces", "dans", "de", "des", "du", "elle", "en", "est", "et", "il",
+            "je", "la", "le", "les", "leur", "lui", "mais", "me", "même", "ne", "nous", "on", "ou", "par",
+            "pas", "pour", "qu", "que", "qui", "sa", "se", "ses", "son", "sur", "ta", "te", "un", "une",
+            "vous",
+        ],
+    }
+}
+
+pub struct StemFilter {
+    language: Language,
+}
+
+impl StemFilter {
+    pub fn new(language: Language) -> Self {
+        StemFilter { language }
+    }
+}
+
+impl TokenFilter for StemFilter {
+    fn filter(&self, tokens: Vec<String>) -> Vec<String> {
+        tokens
+            .into_iter()
+            .map(|token| match self.language {
+                Language::English => stem_english(&token),
+                Language::German => stem_german(&token),
+                Language::French => stem_french(&token),
+            })
+            .collect()
+    }
+}
+
+pub struct NGramFilter {
+    min_gram: usize,
+    max_gram: usize,
+    edge: bool,
+}
+
+impl NGramFilter {
+    pub fn new(config: NGramConfig) -> Self {
+        let min_gram = config.min_gram.max(1);
+        NGramFilter {
+            min_gram,
+            max_gram: config.max_gram.max(min_gram),
+            edge: config.edge,
+        }
+    }
+}
+
+impl TokenFilter for NGramFilter {
+    fn filter(&self, tokens: Vec<String>) -> Vec<String> {
+        let mut grams = Vec::new();
+        for token in tokens {
+            let chars: Vec<char> = token.chars().collect();
+            if chars.len() < self.min_gram {
+                grams.push(token);
+                continue;
+            }
+
+            let starts = if self.edge { 1 } else { chars.len() };
+            for start in 0..starts {
+                for size in self.min_gram..=self.max_gram {
+                    if start + size > chars.len() {
+                        break;
+                    }
+                    grams.push(chars[start..start + size].iter().collect());
+                }
+            }
+        }
+        grams
+    }
+}
+
+fn stem_english(word: &str) -> String {
+    if word.len() <= 2 || !word.bytes().all(|b| b.is_ascii_lowercase()) {
+        return word.to_string();
+    }
+
+    let mut w = word.as_bytes().to_vec();
+    porter_step1(&mut w);
+    porter_step2(&mut w);
+    porter_step3(&mut w);
+    porter_step4(&mut w);
+    porter_step5(&mut w);
+    String::from_utf8(w).unwrap_or_else(|_| word.to_string())
+}
+
+fn is_consonant(w: &[u8], i: usize) -> bool {
+    match w[i] {
+        b'a' | b'e' | b'i' | b'o' | b'u' => false,
+        b'y' => i == 0 || !is_consonant(w, i - 1),
+        _ => true,
+    }
+}
+
+fn measure(w: &[u8]) -> usize {
+    let mut i = 0;
+    let mut m = 0;
+    while i < w.len() && is_consonant(w, i) {
+        i += 1;
+    }
+    loop {
+        while i < w.len() && !is_consonant(w, i) {
+            i += 1;
+        }
+        if i >= w.len() {
+            return m;
+        }
+        while i < w.len() && is_consonant(w, i) {
+            i += 1;
+        }
+        m += 1;
+    }
+}
+
+fn has_vowel(w: &[u8]) -> bool {
+    (0..w.len()).any(|i| !is_consonant(w, i))
+}
+
+fn ends_double_consonant(w: &[u8]) -> bool {
+    let n = w.len();
+    n >= 2 && w[n - 1] == w[n - 2] && is_consonant(w, n - 1)
+}
+
+fn ends_cvc(w: &[u8]) -> bool {
+    let n = w.len();
+    n >= 3
+        && is_consonant(w, n - 3)
+        && !is_consonant(w, n - 2)
+        && is_consonant(w, n - 1)
+        && !matches!(w[n - 1], b'w' | b'x' | b'y')
+}
+
+fn replace_suffix(w: &mut Vec<u8>, rules: &[(&str, &str)], condition: impl Fn(&[u8]) -> bool) {
+    for (suffix, replacement) in rules {
+        if w.ends_with(suffix.as_bytes()) {
+            let stem = w.len() - suffix.len();
+            if condition(&w[..stem]) {
+                w.truncate(stem);
+                w.extend_from_slice(replacement.as_bytes());
+            }
+            return;
+        }
+    }
+}
+
+fn porter_step1(w: &mut Vec<u8>) {
+    replace_suffix(w, &[("sses", "ss"), ("ies", "i"), ("ss", "ss"), ("s", "")], |_| true);
+
+    if w.ends_with(b"eed") {
+        if measure(&w[..w.len() - 3]) > 0 {
+            w.pop();
+        }
+    } else {
+        for suffix in [&b"ed"[..], &b"ing"[..]] {
+            if w.ends_with(suffix) && has_vowel(&w[..w.len() - suffix.len()]) {
+                w.truncate(w.len() - suffix.len());
+                if w.ends_with(b"at") || w.ends_with(b"bl") || w.ends_with(b"iz") {
+                    w.push(b'e');
+                } else if ends_double_consonant(w) && !matches!(w[w.len() - 1], b'l' | b's' | b'z') {
+                    w.pop();
+                } else if measure(w) == 1 && ends_cvc(w) {
+                    w.push(b'e');
+                }
+                break;
+            }
+        }
+    }
+
+    if w.ends_with(b"y") && has_vowel(&w[..w.len() - 1]) {
+        let last = w.len() - 1;
+        w[last] = b'i';
+    }
+}
+
+fn porter_step2(w: &mut Vec<u8>) {
+    replace_suffix(
+        w,
+        &[
+            ("ational", "ate"),
+            ("tional", "tion"),
+            ("enci", "ence"),
+            ("anci", "ance"),
+            ("izer", "ize"),
+            ("abli", "able"),
+            ("alli", "al"),
+            ("entli", "ent"),
+            ("eli", "e"),
+            ("ousli", "ous"),
+            ("ization", "ize"),
+            ("ation", "ate"),
+            ("ator", "ate"),
+            ("alism", "al"),
+            ("iveness", "ive"),
+            ("fulness", "ful"),
+            ("ousness", "ous"),
+            ("aliti", "al"),
+            ("iviti", "ive"),
+            ("biliti", "ble"),
+        ],
+        |stem| measure(stem) > 0,
+    );
+}
+
+fn porter_step3(w: &mut Vec<u8>) {
+    replace_suffix(
+        w,
+        &[
+            ("icate", "ic"),
+            ("ative", ""),
+            ("alize", "al"),
+            ("iciti", "ic"),
+            ("ical", "ic"),
+            ("ful", ""),
+            ("ness", ""),
+        ],
+        |stem| measure(stem) > 0,
+    );
+}
+
+fn porter_step4(w: &mut Vec<u8>) {
+    if w.ends_with(b"ion") {
+        let stem = &w[..w.len() - 3];
+        if measure(stem) > 1 && (stem.ends_with(b"s") || stem.ends_with(b"t")) {
+            w.truncate(w.len() - 3);
+        }
+        return;
+    }
+
+    let suffixes = [
+        "al", "ance", "ence", "er", "ic", "able", "ible", "ant", "ement", "ment", "ent", "ou", "ism", "ate",
+        "iti", "ous", "ive", "ize",
+    ];
+    let rules: Vec<(&str, &str)> = suffixes.iter().map(|suffix| (*suffix, "")).collect();
+    replace_suffix(w, &rules, |stem| measure(stem) > 1);
+}
+
+fn porter_step5(w: &mut Vec<u8>) {
+    if w.ends_with(b"e") {
+        let stem = &w[..w.len() - 1];
+        let m = measure(stem);
+        if m > 1 || (m == 1 && !ends_cvc(stem)) {
+            w.pop();
+        }
+    }
+    if w.ends_with(b"ll") && measure(w) > 1 {
+        w.pop();
+    }
+}
+
+fn stem_german(word: &str) -> String {
+    let mut s: Vec<char> = word
+        .chars()
+        .map(|c| match c {
+            'ä' | 'à' | 'á' | 'â' => 'a',
+            'ö' | 'ò' | 'ó' | 'ô' => 'o',
+            'ï' | 'ì' | 'í' | 'î' => 'i',
+            'ü' | 'ù' | 'ú' | 'û' => 'u',
+            c => c,
+        })
+        .collect();
+
+    let ends = |s: &[char], suffix: &str| s.iter().rev().zip(suffix.chars().rev()).all(|(a, b)| *a == b);
+    let s_ending = |c: char| matches!(c, 'b' | 'd' | 'f' | 'g' | 'h' | 'k' | 'l' | 'm' | 'n' | 'r' | 't');
+    let st_ending = |c: char| matches!(c, 'b' | 'd' | 'f' | 'g' | 'h' | 'k' | 'l' | 'm' | 'n' | 't');
+
+    let len = s.len();
+    let len = if len > 5 && ends(&s, "ern") {
+        len - 3
+    } else if len > 4 && ["em", "en", "er", "es"].iter().any(|suffix| ends(&s, suffix)) {
+        len - 2
+    } else if len > 3 && (ends(&s, "e") || (ends(&s, "s") && s_ending(s[len - 2]))) {
+        len - 1
+    } else {
+        len
+    };
+    s.truncate(len);
+
+    let len = if len > 5 && ends(&s, "est") {
+        len - 3
+    } else if len > 4 && (ends(&s, "er") || ends(&s, "en") || (ends(&s, "st") && st_ending(s[len - 3]))) {
+        len - 2
+    } else {
+        len
+    };
+    s.truncate(len);
+    s.into_iter().collect()
+}
+
+fn stem_french(word: &str) -> String {
+    let mut s: Vec<char> = word.chars().collect();
+    if s.len() < 6 {
+        return word.to_string();
+    }
+
+    if s[s.len() - 1] == 'x' {
+        let len = s.len();
+        if s[len - 3] == 'a' && s[len - 2] == 'u' {
+            s[len - 2] = 'l';
+        }
+        s.pop();
+        return s.into_iter().collect();
+    }
+
+    for suffix in ['s', 'r', 'e', 'é'] {
+        if s.last() == Some(&suffix) {
+            s.pop();
+        }
+    }
+    let len = s.len();
+    if s[len - 1] == s[len - 2] {
+        s.pop();
+    }
+    s.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::search::SearchIndex;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_filter_pipeline() {
+        let config = AnalyzerConfig {
+            ascii_folding: true,
+            stemmer: Some(Language::English),
+            stop_words: Some(Language::English),
+            ..AnalyzerConfig::default()
+        };
+        let analyzer = TextAnalyzer::from_config(&config);
+        assert_eq!(
+            analyzer.analyze("The Cafés were RUNNING relational databases"),
+            vec!["cafe", "were", "run", "relat", "databas"]
+        );
+        assert_eq!(analyzer.analyze("received"), analyzer.analyze("receive"));
+        assert_eq!(analyzer.normalize("Café"), "cafe");
+
+        let german = TextAnalyzer::from_config(&AnalyzerConfig {
+            stemmer: Some(Language::German),
+            stop_words: Some(Language::German),
+            ..AnalyzerConfig::default()
+        });
+        assert_eq!(german.analyze("die Häuser"), german.analyze("Haus"));
+
+        let french = TextAnalyzer::from_config(&AnalyzerConfig {
+            stemmer: Some(Language::French),
+            ..AnalyzerConfig::default()
+        });
+        assert_eq!(french.analyze("chevaux"), french.analyze("cheval"));
+    }
+
+    #[test]
+    fn test_ngrams() {
+        let ngram = |edge| {
+            TextAnalyzer::new(WhitespaceTokenizer).filter(NGramFilter::new(NGramConfig {
+                min_gram: 2,
+                max_gram: 3,
+                edge,
+            }))
+        };
+        assert_eq!(ngram(true).analyze("fabric a"), vec!["fa", "fab", "a"]);
+        assert_eq!(ngram(false).analyze("abcd"), vec!["ab", "abc", "bc", "bcd", "cd"]);
+    }
+
+    #[test]
+    fn test_index_uses_configured_analyzers() {
+        let mut config = Config::default().search;
+        config.analyzer.stemmer = Some(Language::English);
+        config.field_analyzers.insert(
+            "author".to_string(),
+            AnalyzerConfig {
+                ascii_folding: true,
+                ..AnalyzerConfig::default()
+            },
+        );
+        let mut index = SearchIndex::with_config(config);
+        let metadata = HashMap::from([("author".to_string(), "Zoë Brontë".to_string())]);
+        index.index_data("a.txt", b"Indexing connected documents", Some(metadata));
+
+        let keys = |query| -> Vec<String> { index.query(query, 10).unwrap().into_iter().map(|r| r.key).collect() };
+        assert_eq!(keys("connection"), vec!["a.txt"]);
+        assert_eq!(keys("\"connecting document\""), vec!["a.txt"]);
+        assert_eq!(keys("author:\"zoe bronte\""), vec!["a.txt"]);
+    }
+}
@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+use std::thread;
+
+use crate::config::PerformanceConfig;
+use crate::extract::ExtractorRegistry;
+
+use super::inverted::InvertedIndex;
+
+pub type BatchDocument = (String, Vec<u8>, Option<HashMap<String, String>>);
+
+pub(super) struct PreparedDocument {
+    pub key: String,
+    pub data: Option<Vec<u8>>,
+    pub text: Option<String>,
+    pub extracted: HashMap<String, String>,
+    pub metadata: Option<HashMap<String, String>>,
+}
+
+impl PreparedDocument {
+    pub fn new(
+        extractors: &ExtractorRegistry,
+        key: String,
+        data: Vec<u8>,
+        metadata: Option<HashMap<String, String>>,
+    ) -> Self {
+        let (text, extracted) = match extractors.extract(&key, &data) {
+            Some(content) => (Some(content.text), content.metadata),
+            None => (std::str::from_utf8(&data).ok().map(str::to_string), HashMap::new()),
+        };
+        PreparedDocument {
+            key,
+            data: Some(data),
+            text,
+            extracted,
+            metadata,
+        }
+    }
+
+    pub fn restored(key: String, text: Option<String>, metadata: HashMap<String, String>) -> Self {
+        PreparedDocument {
+            key,
+            data: None,
+            text,
+            extracted: HashMap::new(),
+            metadata: Some(metadata),
+        }
+    }
+}
+
+pub(super) struct Segment {
+    pub content: InvertedIndex,
+    pub keys: InvertedIndex,
+    pub documents: Vec<PreparedDocument>,
+}
+
+pub struct PreparedBatch {
+    pub(super) segments: Vec<Segment>,
+}
+
+pub struct BatchBuilder {
+    extractors: ExtractorRegistry,
+    content: InvertedIndex,
+    keys: InvertedIndex,
+}
+
+impl BatchBuilder {
+    pub(super) fn new(extractors: ExtractorRegistry, content: &InvertedIndex, keys: &InvertedIndex) -> Self {
+        BatchBuilder {
+            extractors,
+            content: content.segment(),
+            keys: keys.segment(),
+        }
+    }
+
+    pub fn prepare<I>(&self, documents: I, performance: &PerformanceConfig) -> PreparedBatch
+    where
+        I: IntoIterator<Item = BatchDocument>,
+    {
+        let batch_size = performance.batch_size.max(1);
+        let mut documents = documents.into_iter();
+        let mut segments = Vec::new();
+
+        loop {
+            let batch: Vec<BatchDocument> = documents.by_ref().take(batch_size).collect();
+            if batch.is_empty() {
+                break;
+            }
+            segments.extend(build_segments(
+                batch,
+                performance.worker_threads,
+                &self.extractors,
+                &self.content,
+                &self.keys,
+            ));
+        }
+        PreparedBatch { segments }
+    }
+}
+
+fn build_segments(
+    batch: Vec<BatchDocument>,
+    workers: usize,
+    extractors: &ExtractorRegistry,
+    content: &InvertedIndex,
+    keys: &InvertedIndex,
+) -> Vec<Segment> {
+    let workers = workers.clamp(1, batch.len().max(1));
+    if workers == 1 {
+        return vec![build_segment(batch, extractors, content, keys)];
+    }
+
+    let chunk_size = batch.len().div_ceil(workers);
+    let mut chunks = Vec::with_capacity(workers);
+    let mut documents = batch.into_iter();
+    loop {
+        let chunk: Vec<BatchDocument> = documents.by_ref().take(chunk_size).collect();
+        if chunk.is_empty() {
+            break;
+        }
+        chunks.push(chunk);
+    }
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = chunks
+            .into_iter()
+            .map(|chunk| scope.spawn(move || build_segment(chunk, extractors, content, keys)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("indexing worker panicked"))
+            .collect()
+    })
+}
+
+fn build_segment(
+    chunk: Vec<BatchDocument>,
+    extractors: &ExtractorRegistry,
+    content: &InvertedIndex,
+    keys: &InvertedIndex,
+) -> Segment {
+    let mut segment = Segment {
+        content: content.segment(),
+        keys: keys.segment(),
+        documents: Vec::with_capacity(chunk.len()),
+    };
+
+    for (key, data, metadata) in chunk {
+        let document = PreparedDocument::new(extractors, key, data, metadata);
+        segment.keys.index_document(&document.key, &document.key);
+        match &document.text {
+            Some(text) => segment.content.index_document(&document.key, text),
+            None => {
+                segment.content.remove_document(&document.key);
+            }
+        }
+        segment.documents.push(document);
+    }
+    segment
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Config;
+    use crate::search::SearchIndex;
+
+    #[test]
+    fn test_batch_matches_sequential_indexing() {
+        let documents: Vec<(String, Vec<u8>, Option<_>)> = (0..50)
+            .map(|i| {
+                let text = format!("document {} about topic{} and shared words", i, i % 7);
+                (format!("doc-{}.txt", i), text.into_bytes(), None)
+            })
+            .chain(std::iter::once(("doc-3.txt".to_string(), b"replaced".to_vec(), None)))
+            .collect();
+
+        let mut performance = Config::default().performance;
+        performance.worker_threads = 4;
+        performance.batch_size = 16;
+
+        let mut batched = SearchIndex::new();
+        assert_eq!(batched.index_batch(documents.clone(), &performance), 51);
+
+        let mut sequential = SearchIndex::new();
+        for (key, data, metadata) in &documents {
+            sequential.index_data(key, data, metadata.clone());
+        }
+
+        for query in ["topic3", "shared words", "replaced", "document", "doc*"] {
+            let expected = sequential.query(query, 100).unwrap();
+            let actual = batched.query(query, 100).unwrap();
+            assert_eq!(
+                actual.iter().map(|r| (&r.key, r.score)).collect::<Vec<_>>(),
+                expected.iter().map(|r| (&r.key, r.score)).collect::<Vec<_>>(),
+                "query {}",
+                query
+            );
+        }
+        assert!(batched.query("\"document 3 about\"", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_batch_reindex_without_tokens_drops_old_terms() {
+        let mut index = SearchIndex::new();
+        index.index_data("blank.txt", b"alpha beta", None);
+        index.index_data("spaces.txt", b"gamma delta", None);
+
+        let performance = Config::default().performance;
+        let documents = vec![
+            ("blank.txt".to_string(), Vec::new(), None),
+            ("spaces.txt".to_string(), b"   ".to_vec(), None),
+        ];
+        assert_eq!(index.index_batch(documents, &performance), 2);
+
+        for term in ["alpha", "beta", "gamma", "delta"] {
+            assert!(index.search_content(term, 10).is_empty(), "term {}", term);
+        }
+        assert!(index.contains("blank.txt") && index.contains("spaces.txt"));
+    }
+}
@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+
+use super::query::FieldValue;
+
+pub const MAX_FACET_VALUES: usize = 10;
+
+#[derive(Debug, Clone)]
+pub struct MetadataFilter {
+    pub field: String,
+    pub value: FieldValue,
+}
+
+impl MetadataFilter {
+    pub fn equals(field: &str, value: &str) -> Self {
+        MetadataFilter {
+            field: field.to_string(),
+            value: FieldValue::Exact(value.to_string()),
+        }
+    }
+
+    pub fn prefix(field: &str, prefix: &str) -> Self {
+        MetadataFilter {
+            field: field.to_string(),
+            value: FieldValue::Prefix(prefix.to_string()),
+        }
+    }
+
+    pub fn range(field: &str, lower: Option<f64>, upper: Option<f64>) -> Self {
+        MetadataFilter {
+            field: field.to_string(),
+            value: FieldValue::Range {
+                lower,
+                upper,
+                include_lower: true,
+                include_upper: true,
+            },
+        }
+    }
+
+    pub fn matches(&self, metadata: Option<&HashMap<String, String>>) -> bool {
+        metadata
+            .and_then(|meta| meta.get(&self.field))
+            .is_some_and(|value| self.value.matches(value))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: usize,
+}
+
+pub fn compute_facets<'a, I>(
+    documents: I,
+    fields: &[&str],
+    top_n: usize,
+) -> HashMap<String, Vec<FacetCount>>
+where
+    I: IntoIterator<Item = &'a HashMap<String, String>>,
+{
+    let mut counts: HashMap<&str, HashMap<&str, usize>> = HashMap::new();
+
+    for metadata in documents {
+        for field in fields {
+            if let Some(value) = metadata.get(*field) {
+                *counts.entry(field).or_default().entry(value.as_str()).or_insert(0) += 1;
+            }
+        }
+    }
+
+    fields
+        .iter()
+        .map(|field| {
+            let mut facet: Vec<FacetCount> = counts
+                .get(field)
+                .map(|values| {
+                    values
+                        .iter()
+                        .map(|(value, count)| FacetCount {
+                            value: value.to_string(),
+                            count: *count,
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            facet.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+            facet.truncate(top_n);
+            (field.to_string(), facet)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_facet_counts() {
+        let docs = [
+            metadata(&[("type", "pdf"), ("folder", "reports")]),
+            metadata(&[("type", "pdf"), ("folder", "inbox")]),
+            metadata(&[("type", "docx"), ("folder", "reports")]),
+            metadata(&[("folder", "reports")]),
+        ];
+
+        let facets = compute_facets(docs.iter(), &["type", "folder", "author"], 1);
+        assert_eq!(facets["type"], vec![FacetCount { value: "pdf".to_string(), count: 2 }]);
+        assert_eq!(facets["folder"][0].count, 3);
+        assert!(facets["author"].is_empty());
+    }
+
+    #[test]
+    fn test_metadata_filter() {
+        let doc = metadata(&[("type", "PDF"), ("size", "42")]);
+        assert!(MetadataFilter::equals("type", "pdf").matches(Some(&doc)));
+        assert!(MetadataFilter::range("size", Some(10.0), None).matches(Some(&doc)));
+        assert!(!MetadataFilter::range("size", None, Some(10.0)).matches(Some(&doc)));
+        assert!(!MetadataFilter::prefix("author", "jo").matches(Some(&doc)));
+        assert!(!MetadataFilter::equals("type", "pdf").matches(None));
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+
+const DEFAULT_RRF_K: f32 = 60.0;
+
+#[derive(Debug, Clone, Copy)]
+pub enum FusionMethod {
+    ReciprocalRank { k: f32 },
+    Linear { lexical_weight: f32, vector_weight: f32 },
+}
+
+impl Default for FusionMethod {
+    fn default() -> Self {
+        FusionMethod::ReciprocalRank { k: DEFAULT_RRF_K }
+    }
+}
+
+pub fn reciprocal_rank_fusion(lists: &[Vec<(String, f32)>], k: f32) -> Vec<(String, f32)> {
+    let mut scores: HashMap<&str, f32> = HashMap::new();
+
+    for list in lists {
+        for (rank, (key, _)) in list.iter().enumerate() {
+            *scores.entry(key.as_str()).or_insert(0.0) += 1.0 / (k + rank as f32 + 1.0);
+        }
+    }
+
+    sorted(scores)
+}
+
+pub fn linear_fusion(
+    lexical: &[(String, f32)],
+    vector: &[(String, f32)],
+    lexical_weight: f32,
+    vector_weight: f32,
+) -> Vec<(String, f32)> {
+    let total = lexical_weight + vector_weight;
+    let (lexical_weight, vector_weight) = if total > 0.0 {
+        (lexical_weight / total, vector_weight / total)
+    } else {
+        (0.5, 0.5)
+    };
+
+    let mut scores: HashMap<&str, f32> = HashMap::new();
+    for (key, score) in lexical {
+        *scores.entry(key.as_str()).or_insert(0.0) += lexical_weight * score;
+    }
+    for (key, score) in vector {
+        *scores.entry(key.as_str()).or_insert(0.0) += vector_weight * score;
+    }
+
+    sorted(scores)
+}
+
+pub fn normalize_scores(results: &mut [(String, f32)]) {
+    let max = results.iter().map(|(_, score)| *score).fold(0.0f32, f32::max);
+    if max > 0.0 {
+        for (_, score) in results.iter_mut() {
+            *score /= max;
+        }
+    }
+}
+
+fn sorted(scores: HashMap<&str, f32>) -> Vec<(String, f32)> {
+    let mut results: Vec<(String, f32)> = scores
+        .into_iter()
+        .map(|(key, score)| (key.to_string(), score))
+        .collect();
+    results.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(entries: &[(&str, f32)]) -> Vec<(String, f32)> {
+        entries.iter().map(|(k, s)| (k.to_string(), *s)).collect()
+    }
+
+    #[test]
+    fn test_reciprocal_rank_fusion() {
+        let lexical = list(&[("a", 0.9), ("b", 0.5), ("c", 0.1)]);
+        let vector = list(&[("b", 0.99), ("c", 0.8)]);
+
+        let fused = reciprocal_rank_fusion(&[lexical, vector], DEFAULT_RRF_K);
+        assert_eq!(fused[0].0, "b");
+        assert_eq!(fused.len(), 3);
+    }
+
+    #[test]
+    fn test_linear_fusion() {
+        let lexical = list(&[("a", 1.0), ("b", 0.2)]);
+        let vector = list(&[("b", 1.0)]);
+
+        let fused = linear_fusion(&lexical, &vector, 1.0, 0.0);
+        assert_eq!(fused[0].0, "a");
+
+        let fused = linear_fusion(&lexical, &vector, 0.2, 0.8);
+        assert_eq!(fused[0].0, "b");
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use super::analyzer::{Analyzer, TextAnalyzer};
+
+const BM25_K1: f32 = 1.2;
+const BM25_B: f32 = 0.75;
+
+pub struct InvertedIndex {
+    analyzer: Arc<dyn Analyzer>,
+    postings: BTreeMap<String, HashMap<String, u32>>,
+    doc_terms: HashMap<String, Vec<String>>,
+    doc_lengths: HashMap<String, usize>,
+    total_length: usize,
+}
+
+impl Default for InvertedIndex {
+    fn default() -> Self {
+        Self::with_analyzer(Arc::new(TextAnalyzer::standard()))
+    }
+}
+
+impl InvertedIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_analyzer(analyzer: Arc<dyn Analyzer>) -> Self {
+        InvertedIndex {
+            analyzer,
+            postings: BTreeMap::new(),
+            doc_terms: HashMap::new(),
+            doc_lengths: HashMap::new(),
+            total_length: 0,
+        }
+    }
+
+    pub fn segment(&self) -> InvertedIndex {
+        Self::with_analyzer(self.analyzer.clone())
+    }
+
+    pub fn merge(&mut self, segment: InvertedIndex) {
+        for key in segment.doc_terms.keys() {
+            self.remove_document(key);
+        }
+        for (term, mut docs) in segment.postings {
+            match self.postings.entry(term) {
+                Entry::Vacant(entry) => {
+                    entry.insert(docs);
+                }
+                Entry::Occupied(mut entry) => {
+                    if entry.get().len() < docs.len() {
+                        std::mem::swap(entry.get_mut(), &mut docs);
+                    }
+                    entry.get_mut().extend(docs);
+                }
+            }
+        }
+        self.doc_terms.extend(segment.doc_terms);
+        self.doc_lengths.extend(segment.doc_lengths);
+        self.total_length += segment.total_length;
+    }
+
+    pub fn analyze(&self, text: &str) -> Vec<String> {
+        self.analyzer.analyze(text)
+    }
+
+    pub fn normalize(&self, term: &str) -> String {
+        self.analyzer.normalize(term)
+    }
+
+    pub fn index_document(&mut self, key: &str, text: &str) {
+        let tokens = self.analyze(text);
+        let mut frequencies: HashMap<String, u32> = HashMap::new();
+        for token in &tokens {
+            *frequencies.entry(token.clone()).or_insert(0) += 1;
+        }
+        self.restore_document(key, frequencies.into_iter().collect(), tokens.len());
+    }
+
+    pub fn restore_document(&mut self, key: &str, terms: Vec<(String, u32)>, length: usize) {
+        self.remove_document(key);
+        if terms.is_empty() {
+            return;
+        }
+
+        for (term, tf) in &terms {
+            self.postings
+                .entry(term.clone())
+                .or_default()
+                .insert(key.to_string(), *tf);
+        }
+
+        self.doc_terms.insert(key.to_string(), terms.into_iter().map(|(term, _)| term).collect());
+        self.doc_lengths.insert(key.to_string(), length);
+        self.total_length += length;
+    }
+
+    pub fn document_terms(&self, key: &str) -> Vec<(String, u32)> {
+        let mut terms: Vec<(String, u32)> = self
+            .doc_terms
+            .get(key)
+            .into_iter()
+            .flatten()
+            .filter_map(|term| Some((term.clone(), *self.postings.get(term)?.get(key)?)))
+            .collect();
+        terms.sort_unstable();
+        terms
+    }
+
+    pub fn document_length(&self, key: &str) -> usize {
+        self.doc_lengths.get(key).copied().unwrap_or(0)
+    }
+
+    pub fn remove_document(&mut self, key: &str) -> bool {
+        let terms = match self.doc_terms.remove(key) {
+            Some(terms) => terms,
+            None => return false,
+        };
+
+        for term in terms {
+            if let Some(docs) = self.postings.get_mut(&term) {
+                docs.remove(key);
+                if docs.is_empty() {
+                    self.postings.remove(&term);
+                }
+            }
+        }
+
+        if let Some(length) = self.doc_lengths.remove(key) {
+            self.total_length -= length;
+        }
+        true
+    }
+
+    pub fn search(&self, query: &str, limit: usize) -> Vec<(String, f32)> {
+        let mut results = self.score_terms(&self.analyze(query));
+        results.truncate(limit);
+        results
+    }
+
+    pub fn search_term(&self, term: &str) -> Vec<(String, f32)> {
+        self.score_terms(&[term.to_string()])
+    }
+
+    fn score_terms(&self, terms: &[String]) -> Vec<(String, f32)> {
+        let doc_count = self.doc_lengths.len();
+        if doc_count == 0 {
+            return Vec::new();
+        }
+
+        let avg_length = self.total_length as f32 / doc_count as f32;
+        let mut scores: HashMap<&str, f32> = HashMap::new();
+
+        for term in terms {
+            let docs = match self.postings.get(term) {
+                Some(docs) => docs,
+                None => continue,
+            };
+
+            let df = docs.len() as f32;
+            let idf = ((doc_count as f32 - df + 0.5) / (df + 0.5) + 1.0).ln();
+
+            for (key, &tf) in docs {
+                let tf = tf as f32;
+                let length = self.doc_lengths[key] as f32;
+                let norm = BM25_K1 * (1.0 - BM25_B + BM25_B * length / avg_length);
+                *scores.entry(key.as_str()).or_insert(0.0) += idf * tf * (BM25_K1 + 1.0) / (tf + norm);
+            }
+        }
+
+        let mut results: Vec<(String, f32)> = scores
+            .into_iter()
+            .map(|(key, score)| (key.to_string(), score))
+            .collect();
+        results.sort_by(|a, b| b.1.total_cmp(&a.1));
+        results
+    }
+
+    pub fn terms_with_prefix(&self, prefix: &str) -> Vec<&str> {
+        self.postings
+            .range(prefix.to_string()..)
+            .take_while(|(term, _)| term.starts_with(prefix))
+            .map(|(term, _)| term.as_str())
+            .collect()
+    }
+
+    pub fn terms(&self) -> impl Iterator<Item = &str> {
+        self.postings.keys().map(|term| term.as_str())
+    }
+
+    pub fn documents_with_term(&self, term: &str) -> Vec<&str> {
+        self.postings
+            .get(term)
+            .map(|docs| docs.keys().map(|key| key.as_str()).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn document_frequency(&self, term: &str) -> usize {
+        self.postings.get(term).map_or(0, |docs| docs.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.doc_lengths.is_empty()
+    }
+}
+
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(|token| token.to_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bm25_ranking() {
+        let mut index = InvertedIndex::new();
+        index.index_document("a", "the quick brown fox");
+        index.index_document("b", "the lazy dog sleeps all day, lazy lazy dog");
+        index.index_document("c", "a fox and a dog");
+
+        let results = index.search("lazy dog", 10);
+        assert_eq!(results[0].0, "b");
+        assert_eq!(results.len(), 2);
+
+        let results = index.search("fox", 10);
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|(key, _)| key != "b"));
+    }
+
+    #[test]
+    fn test_reindex_and_remove() {
+        let mut index = InvertedIndex::new();
+        index.index_document("a", "alpha beta");
+        index.index_document("a", "gamma");
+        assert_eq!(index.document_frequency("alpha"), 0);
+        assert_eq!(index.document_frequency("gamma"), 1);
+
+        assert!(index.remove_document("a"));
+        assert!(index.is_empty());
+        assert!(index.search("gamma", 10).is_empty());
+    }
+}
@@ -1,6 +1,5 @@
-use std::time::{Duration, Instant};
+use std::time::Duration;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
 use std::collections::VecDeque;
 use std::time::SystemTime;
 
@@ -9,15 +8,17 @@ const WINDOW_SIZE: usize = 100;
 pub struct SearchMetrics {
     total_searches: AtomicU64,
     total_search_time: AtomicU64,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
     recent_searches: parking_lot::Mutex<VecDeque<SearchStats>>,
 }
 
 #[derive(Clone, Debug)]
-struct SearchStats {
-    query: String,
-    duration: Duration,
-    timestamp: SystemTime,
-    result_count: usize,
+pub struct SearchStats {
+    pub query: String,
+    pub duration: Duration,
+    pub timestamp: SystemTime,
+    pub result_count: usize,
 }
 
 impl Default for SearchMetrics {
@@ -31,6 +32,8 @@ impl SearchMetrics {
         SearchMetrics {
             total_searches: AtomicU64::new(0),
             total_search_time: AtomicU64::new(0),
+            cache_hits: AtomicU64::new(0),
+            cache_misses: AtomicU64::new(0),
             recent_searches: parking_lot::Mutex::new(VecDeque::with_capacity(WINDOW_SIZE)),
         }
     }
@@ -58,6 +61,28 @@ impl SearchMetrics {
         });
     }
 
+    pub fn record_cache_access(&self, hit: bool) {
+        let counter = if hit { &self.cache_hits } else { &self.cache_misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn get_cache_hits(&self) -> u64 {
+        self.cache_hits.load(Ordering::Relaxed)
+    }
+
+    pub fn get_cache_misses(&self) -> u64 {
+        self.cache_misses.load(Ordering::Relaxed)
+    }
+
+    pub fn get_cache_hit_rate(&self) -> Option<f64> {
+        let hits = self.get_cache_hits();
+        let total = hits + self.get_cache_misses();
+        if total == 0 {
+            return None;
+        }
+        Some(hits as f64 / total as f64)
+    }
+
     pub fn get_average_search_time(&self) -> Option<Duration> {
         let total = self.total_searches.load(Ordering::Relaxed);
         if total == 0 {
@@ -77,6 +102,7 @@ impl SearchMetrics {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::Arc;
     use std::thread;
     use std::time::Duration;
 
@@ -104,5 +130,10 @@ mod tests {
         assert_eq!(recent.len(), 1);
         assert_eq!(recent[0].query, "test");
         assert_eq!(recent[0].result_count, 5);
+        
+        assert_eq!(metrics.get_cache_hit_rate(), None);
+        metrics.record_cache_access(true);
+        metrics.record_cache_access(false);
+        assert_eq!(metrics.get_cache_hit_rate(), Some(0.5));
     }
 }
@@ -1,180 +1,1043 @@
-use std::collections::HashMap;
-use std::sync::Arc;
-use std::sync::RwLock;
-use std::ffi::{CStr, CString};
-use std::os::raw::c_char;
-use std::ptr;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock, RwLockReadGuard};
 use std::time::Instant;
 
 mod algorithms;
+mod analyzer;
+mod batch;
+mod facets;
+mod hybrid;
+mod inverted;
 mod metrics;
+mod query;
+mod segment;
+mod suggest;
+mod typo;
+mod vector;
 
+use crate::config::{Config, MatcherKind, PerformanceConfig, SearchConfig};
+use crate::extract::ExtractorRegistry;
 use algorithms::*;
-use metrics::SearchMetrics;
+use batch::PreparedDocument;
+use inverted::tokenize;
+
+pub use analyzer::{
+    Analyzer, AsciiFoldingFilter, LowercaseFilter, NGramFilter, StemFilter, StopWordFilter, TextAnalyzer,
+    TokenFilter, Tokenizer, WhitespaceTokenizer, WordTokenizer,
+};
+pub use batch::{BatchBuilder, BatchDocument, PreparedBatch};
+pub use facets::{FacetCount, MetadataFilter};
+pub use hybrid::FusionMethod;
+pub use inverted::InvertedIndex;
+pub use metrics::{SearchMetrics, SearchStats};
+pub use query::{parse_query, FieldValue, Query};
+pub use segment::{MergeStatus, SegmentMerger, SegmentReader, SegmentStore, StoredDocument, FORMAT_VERSION};
+pub use suggest::{Suggestion, SuggestionTrie};
+pub use typo::LevenshteinAutomaton;
+pub use vector::VectorIndex;
+
+const POPULARITY_WEIGHT: f32 = 2.0;
+const RECENCY_DECAY: f32 = 0.05;
 
 pub struct SearchIndex {
-    data: HashMap<String, Vec<u8>>,
+    config: SearchConfig,
+    data: HashMap<String, Option<Vec<u8>>>,
+    texts: HashMap<String, String>,
+    checksums: HashMap<String, u32>,
     metadata: HashMap<String, HashMap<String, String>>,
     vector_index: Option<VectorIndex>,
+    inverted_index: InvertedIndex,
+    key_index: InvertedIndex,
+    field_analyzers: HashMap<String, TextAnalyzer>,
+    suggestions: SuggestionTrie,
+    suggested: HashMap<String, Vec<String>>,
+    extractors: ExtractorRegistry,
     metrics: RwLock<SearchMetrics>,
 }
 
-#[derive(Default)]
-struct VectorIndex {
-    vectors: HashMap<String, Vec<f32>>,
-    dimensions: usize,
+impl Default for SearchIndex {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SearchIndex {
     pub fn new() -> Self {
+        Self::with_config(Config::default().search)
+    }
+
+    pub fn with_config(config: SearchConfig) -> Self {
+        let content_analyzer = Arc::new(TextAnalyzer::from_config(&config.analyzer));
+        let field_analyzers = config
+            .field_analyzers
+            .iter()
+            .map(|(field, analyzer)| (field.clone(), TextAnalyzer::from_config(analyzer)))
+            .collect();
+
         SearchIndex {
+            config,
             data: HashMap::new(),
+            texts: HashMap::new(),
+            checksums: HashMap::new(),
             metadata: HashMap::new(),
             vector_index: None,
+            inverted_index: InvertedIndex::with_analyzer(content_analyzer),
+            key_index: InvertedIndex::new(),
+            field_analyzers,
+            suggestions: SuggestionTrie::new(),
+            suggested: HashMap::new(),
+            extractors: ExtractorRegistry::default(),
             metrics: RwLock::new(SearchMetrics::new()),
         }
     }
 
     pub fn index_data(&mut self, key: &str, data: &[u8], metadata: Option<HashMap<String, String>>) -> bool {
-        self.data.insert(key.to_string(), data.to_vec());
+        let document = PreparedDocument::new(&self.extractors, key.to_string(), data.to_vec(), metadata);
+        self.key_index.index_document(key, key);
+        if let Some(text) = &document.text {
+            self.inverted_index.index_document(key, text);
+        }
+        self.store(document);
+        true
+    }
+
+    pub fn index_batch<I>(&mut self, documents: I, performance: &PerformanceConfig) -> usize
+    where
+        I: IntoIterator<Item = BatchDocument>,
+    {
+        let builder = self.batch_builder();
+        let batch_size = performance.batch_size.max(1);
+        let mut documents = documents.into_iter();
+        let mut indexed = 0;
+
+        loop {
+            let batch: Vec<BatchDocument> = documents.by_ref().take(batch_size).collect();
+            if batch.is_empty() {
+                break;
+            }
+            indexed += self.apply_batch(builder.prepare(batch, performance));
+        }
+        indexed
+    }
+
+    pub fn restore_batch(&mut self, documents: Vec<StoredDocument>) -> Result<usize, String> {
+        let mut restored = 0;
+        for document in documents.into_iter().filter(|document| !document.deleted) {
+            self.key_index.index_document(&document.key, &document.key);
+            self.inverted_index
+                .restore_document(&document.key, document.terms, document.length as usize);
+            if let Some(vector) = &document.vector {
+                self.index_vector(&document.key, vector)?;
+            }
+            self.checksums.insert(document.key.clone(), document.checksum);
+            let key = document.key.clone();
+            self.store(PreparedDocument::restored(
+                document.key,
+                document.text,
+                document.metadata,
+            ));
+            self.release_document(&key);
+            restored += 1;
+        }
+        Ok(restored)
+    }
+
+    pub fn batch_builder(&self) -> BatchBuilder {
+        BatchBuilder::new(self.extractors.clone(), &self.inverted_index, &self.key_index)
+    }
+
+    pub fn apply_batch(&mut self, batch: PreparedBatch) -> usize {
+        let mut indexed = 0;
+        for segment in batch.segments {
+            for document in &segment.documents {
+                self.inverted_index.remove_document(&document.key);
+                self.key_index.remove_document(&document.key);
+            }
+            self.inverted_index.merge(segment.content);
+            self.key_index.merge(segment.keys);
+            for document in segment.documents {
+                self.store(document);
+                indexed += 1;
+            }
+        }
+        indexed
+    }
+
+    fn store(&mut self, document: PreparedDocument) {
+        let PreparedDocument {
+            key,
+            data,
+            text,
+            extracted,
+            metadata,
+        } = document;
+
+        let terms: Vec<String> = suggestion_terms(&key, text.as_deref()).into_iter().collect();
+        for term in &terms {
+            self.suggestions.insert(term);
+        }
+        for term in self.suggested.insert(key.clone(), terms).into_iter().flatten() {
+            self.suggestions.remove(&term);
+        }
+
+        match text {
+            Some(text) if data.as_deref() != Some(text.as_bytes()) => {
+                self.texts.insert(key.clone(), text);
+            }
+            Some(_) => {
+                self.texts.remove(&key);
+            }
+            None => {
+                self.texts.remove(&key);
+                self.inverted_index.remove_document(&key);
+            }
+        }
+        if let Some(data) = &data {
+            self.checksums.insert(key.clone(), crc32fast::hash(data));
+        }
+        self.data.insert(key.clone(), data);
+
+        match metadata {
+            Some(meta) => {
+                let mut merged = extracted;
+                merged.extend(meta);
+                self.metadata.insert(key, merged);
+            }
+            None if !extracted.is_empty() => {
+                self.metadata.entry(key).or_default().extend(extracted);
+            }
+            None => {}
+        }
+    }
+
+    pub fn extractors_mut(&mut self) -> &mut ExtractorRegistry {
+        &mut self.extractors
+    }
+
+    pub fn extractors(&self) -> &ExtractorRegistry {
+        &self.extractors
+    }
+
+    pub fn payload(&self, key: &str) -> Option<&[u8]> {
+        self.data.get(key).and_then(Option::as_deref)
+    }
+
+    pub fn release_payload(&mut self, key: &str) -> bool {
+        let Some(data) = self.data.get_mut(key).and_then(Option::take) else {
+            return false;
+        };
+        if !self.texts.contains_key(key) {
+            if let Ok(text) = String::from_utf8(data) {
+                self.texts.insert(key.to_string(), text);
+            }
+        }
+        true
+    }
+
+    pub fn release_document(&mut self, key: &str) -> bool {
+        let Some(data) = self.data.get_mut(key) else {
+            return false;
+        };
+        *data = None;
+        self.texts.remove(key);
+        true
+    }
+
+    pub fn metrics(&self) -> Option<RwLockReadGuard<'_, SearchMetrics>> {
+        self.metrics.read().ok()
+    }
+
+    pub fn document_text(&self, key: &str) -> Option<&str> {
+        match self.texts.get(key) {
+            Some(text) => Some(text),
+            None => self.payload(key).and_then(|data| std::str::from_utf8(data).ok()),
+        }
+    }
+
+    pub fn checksum(&self, key: &str) -> Option<u32> {
+        self.checksums.get(key).copied()
+    }
+
+    pub fn stored_document(&self, key: &str) -> StoredDocument {
+        if !self.contains(key) {
+            return StoredDocument::tombstone(key);
+        }
+        StoredDocument {
+            key: key.to_string(),
+            deleted: false,
+            text: self.document_text(key).map(str::to_string),
+            metadata: self.metadata.get(key).cloned().unwrap_or_default(),
+            vector: self
+                .vector_index
+                .as_ref()
+                .and_then(|index| index.get(key))
+                .map(<[f32]>::to_vec),
+            checksum: self.checksum(key).unwrap_or_default(),
+            length: self.inverted_index.document_length(key) as u32,
+            terms: self.inverted_index.document_terms(key),
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        key: &str,
+        data: Option<&[u8]>,
+        metadata: Option<HashMap<String, String>>,
+    ) -> bool {
+        if !self.data.contains_key(key) {
+            return false;
+        }
+
+        if let Some(data) = data {
+            self.index_data(key, data, None);
+        }
         if let Some(meta) = metadata {
-            self.metadata.insert(key.to_string(), meta);
+            self.metadata.entry(key.to_string()).or_default().extend(meta);
         }
         true
     }
 
+    pub fn remove(&mut self, key: &str) -> bool {
+        if !self.contains(key) {
+            return false;
+        }
+        for term in self.suggested.remove(key).into_iter().flatten() {
+            self.suggestions.remove(&term);
+        }
+        self.data.remove(key);
+        self.texts.remove(key);
+        self.checksums.remove(key);
+
+        self.metadata.remove(key);
+        self.inverted_index.remove_document(key);
+        self.key_index.remove_document(key);
+        self.remove_vector(key);
+        true
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.data.contains_key(key)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.data.keys().map(String::as_str)
+    }
+
+    pub fn get_metadata(&self, key: &str) -> Option<&HashMap<String, String>> {
+        self.metadata.get(key)
+    }
+
     pub fn search(&self, query: &str, limit: usize) -> Vec<SearchResult> {
         let start = Instant::now();
         let mut results = Vec::new();
         
-        for (key, _) in &self.data {
-            if let Some(score) = fuzzy_match(key, query) {
+        for key in self.data.keys() {
+            if let Some((score, positions)) = fuzzy_match_positions(key, query) {
                 results.push(SearchResult {
                     key: key.clone(),
                     score,
                     metadata: self.metadata.get(key).cloned(),
+                    positions,
                 });
             }
         }
         
-        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+        results.sort_by(|a, b| b.score.total_cmp(&a.score));
         results.truncate(limit);
         
         let duration = start.elapsed();
-        if let Ok(mut metrics) = self.metrics.write() {
+        if let Ok(metrics) = self.metrics.write() {
             metrics.record_search(duration);
         }
         
         results
     }
     
-    pub fn vector_search(&self, query: &[f32], k: usize) -> Option<Vec<VectorSearchResult>> {
-        self.vector_index.as_ref().map(|index| {
-            let mut results = Vec::new();
-            
-            for (key, vector) in &index.vectors {
-                let score = cosine_similarity(query, vector);
-                results.push(VectorSearchResult {
-                    key: key.clone(),
-                    score,
-                });
+    pub fn search_content(&self, query: &str, limit: usize) -> Vec<SearchResult> {
+        let start = Instant::now();
+
+        let results: Vec<SearchResult> = self
+            .inverted_index
+            .search(query, limit)
+            .into_iter()
+            .map(|(key, score)| SearchResult {
+                metadata: self.metadata.get(&key).cloned(),
+                key,
+                score,
+                positions: Vec::new(),
+            })
+            .collect();
+
+        let duration = start.elapsed();
+        if let Ok(metrics) = self.metrics.write() {
+            metrics.record_search(duration);
+        }
+
+        results
+    }
+
+    pub fn hybrid_search(
+        &self,
+        query: &str,
+        vector: Option<&[f32]>,
+        limit: usize,
+        fusion: FusionMethod,
+    ) -> Vec<SearchResult> {
+        let start = Instant::now();
+        let limit = limit.min(self.config.max_results);
+        let candidates = limit.saturating_mul(4).max(limit);
+        let min_score = self.config.min_score;
+
+        let fuzzy = if self.config.enable_fuzzy {
+            self.fuzzy_matches(query)
+        } else {
+            Vec::new()
+        };
+
+        let mut content = self.inverted_index.search(query, candidates);
+        hybrid::normalize_scores(&mut content);
+
+        let mut merged: HashMap<&str, f32> = HashMap::with_capacity(fuzzy.len() + content.len());
+        for (key, score) in fuzzy.iter().chain(&content) {
+            let entry = merged.entry(key.as_str()).or_insert(*score);
+            *entry = entry.max(*score);
+        }
+        let mut lexical: Vec<(String, f32)> = merged
+            .into_iter()
+            .filter(|(_, score)| *score >= min_score)
+            .map(|(key, score)| (key.to_string(), score))
+            .collect();
+        lexical.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        lexical.truncate(candidates);
+
+        let mut semantic = Vec::new();
+        if self.config.enable_vector {
+            if let (Some(index), Some(vector)) = (self.vector_index.as_ref(), vector) {
+                semantic = index.search(vector, candidates);
+                semantic.retain(|(_, score)| *score >= min_score);
             }
-            
-            results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
-            results.truncate(k);
-            results
-        })
+        }
+
+        let fused = match fusion {
+            FusionMethod::ReciprocalRank { k } => hybrid::reciprocal_rank_fusion(&[lexical, semantic], k),
+            FusionMethod::Linear { lexical_weight, vector_weight } => {
+                hybrid::linear_fusion(&lexical, &semantic, lexical_weight, vector_weight)
+            }
+        };
+
+        let results = fused
+            .into_iter()
+            .take(limit)
+            .map(|(key, score)| SearchResult {
+                metadata: self.metadata.get(&key).cloned(),
+                positions: fuzzy_match_positions(&key, query)
+                    .map(|(_, positions)| positions)
+                    .unwrap_or_default(),
+                key,
+                score,
+            })
+            .collect();
+
+        let duration = start.elapsed();
+        if let Ok(metrics) = self.metrics.write() {
+            metrics.record_search(duration);
+        }
+
+        results
     }
-}
 
-pub struct SearchResult {
-    pub key: String,
-    pub score: f32,
-    pub metadata: Option<HashMap<String, String>>,
-}
+    pub fn query(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>, String> {
+        self.query_with_texts(query, limit, &HashMap::new())
+    }
 
-pub struct VectorSearchResult {
-    pub key: String,
-    pub score: f32,
-}
+    pub fn query_with_texts(
+        &self,
+        query: &str,
+        limit: usize,
+        texts: &HashMap<String, String>,
+    ) -> Result<Vec<SearchResult>, String> {
+        let start = Instant::now();
+        let parsed = parse_query(query)?;
+        let results = self.rank(&parsed, limit, texts);
 
-static mut GLOBAL_INDEX: Option<Arc<RwLock<SearchIndex>>> = None;
+        let duration = start.elapsed();
+        if let Ok(metrics) = self.metrics.write() {
+            metrics.record_search_with_details(query, duration, results.len());
+        }
 
-#[no_mangle]
-pub extern "C" fn fabric_init() -> bool {
-    unsafe {
-        if GLOBAL_INDEX.is_none() {
-            GLOBAL_INDEX = Some(Arc::new(RwLock::new(SearchIndex::new())));
-            true
-        } else {
-            false
+        Ok(results)
+    }
+
+    pub fn execute(&self, query: &Query, limit: usize) -> Vec<SearchResult> {
+        let start = Instant::now();
+        let results = self.rank(query, limit, &HashMap::new());
+
+        let duration = start.elapsed();
+        if let Ok(metrics) = self.metrics.write() {
+            metrics.record_search(duration);
         }
+
+        results
     }
-}
 
-#[no_mangle]
-pub extern "C" fn fabric_index_data(
-    key: *const c_char,
-    data: *const u8,
-    length: usize,
-) -> bool {
-    unsafe {
-        if GLOBAL_INDEX.is_none() {
-            return false;
+    pub fn phrase_candidates(&self, query: &Query) -> Vec<String> {
+        let mut candidates = HashSet::new();
+        self.collect_phrase_candidates(query, &mut candidates);
+        candidates.into_iter().collect()
+    }
+
+    fn collect_phrase_candidates(&self, query: &Query, candidates: &mut HashSet<String>) {
+        match query {
+            Query::Phrase(words) => {
+                let terms = self.inverted_index.analyze(&words.join(" "));
+                if let Some(first) = terms.first() {
+                    candidates.extend(
+                        self.inverted_index
+                            .documents_with_term(first)
+                            .into_iter()
+                            .filter(|key| self.document_text(key).is_none())
+                            .map(str::to_string),
+                    );
+                }
+            }
+            Query::And(clauses) | Query::Or(clauses) => {
+                for clause in clauses {
+                    self.collect_phrase_candidates(clause, candidates);
+                }
+            }
+            Query::Not(inner) => self.collect_phrase_candidates(inner, candidates),
+            Query::Term(_) | Query::Prefix(_) | Query::Field { .. } => {}
         }
-        
-        let key_cstr = CStr::from_ptr(key);
-        let key_str = match key_cstr.to_str() {
-            Ok(s) => s.to_string(),
-            Err(_) => return false,
+    }
+
+    fn rank(&self, query: &Query, limit: usize, texts: &HashMap<String, String>) -> Vec<SearchResult> {
+        let mut matches: Vec<(String, f32)> = self.evaluate(query, texts).into_iter().collect();
+        matches.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        matches.truncate(limit);
+
+        matches
+            .into_iter()
+            .map(|(key, score)| SearchResult {
+                metadata: self.metadata.get(&key).cloned(),
+                positions: highlight_positions(&key, query),
+                key,
+                score,
+            })
+            .collect()
+    }
+
+    pub fn autocomplete(&self, prefix: &str, limit: usize) -> Vec<Suggestion> {
+        let prefix = prefix.trim();
+        if prefix.is_empty() || limit == 0 {
+            return Vec::new();
+        }
+
+        let recent = match self.metrics.read() {
+            Ok(metrics) => metrics.get_recent_searches(usize::MAX),
+            Err(_) => Vec::new(),
         };
-        
-        let data_slice = std::slice::from_raw_parts(data, length);
-        
-        if let Ok(mut index) = GLOBAL_INDEX.as_ref().unwrap().write() {
-            index.index_data(&key_str, data_slice, None);
-            true
+        let mut popularity: HashMap<String, f32> = HashMap::new();
+        for (age, stats) in recent.iter().filter(|stats| stats.result_count > 0).enumerate() {
+            let weight = 1.0 / (1.0 + age as f32 * RECENCY_DECAY);
+            let query = stats.query.trim().to_lowercase();
+            for token in tokenize(&query) {
+                if token != query {
+                    *popularity.entry(token).or_insert(0.0) += weight;
+                }
+            }
+            *popularity.entry(query).or_insert(0.0) += weight;
+        }
+
+        let mut suggestions: Vec<Suggestion> = self
+            .suggestions
+            .complete(prefix)
+            .into_iter()
+            .map(|(text, count)| {
+                let boost = popularity.get(&text.to_lowercase()).copied().unwrap_or(0.0);
+                Suggestion {
+                    text: text.to_string(),
+                    score: (1.0 + count as f32).ln() + POPULARITY_WEIGHT * boost,
+                }
+            })
+            .collect();
+        suggestions.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.text.len().cmp(&b.text.len()))
+                .then_with(|| a.text.cmp(&b.text))
+        });
+        suggestions.truncate(limit);
+        suggestions
+    }
+
+    pub fn search_faceted(
+        &self,
+        query: &str,
+        filters: &[MetadataFilter],
+        facet_fields: &[&str],
+        limit: usize,
+    ) -> Result<FacetedResults, String> {
+        let start = Instant::now();
+
+        let parsed = if query.trim().is_empty() {
+            None
         } else {
-            false
+            Some(parse_query(query)?)
+        };
+        let mut matches: Vec<(String, f32)> = match &parsed {
+            Some(parsed) => self.evaluate(parsed, &HashMap::new()).into_iter().collect(),
+            None => self.data.keys().map(|key| (key.clone(), 0.0)).collect(),
+        };
+        matches.retain(|(key, _)| filters.iter().all(|filter| filter.matches(self.metadata.get(key))));
+
+        let facets = facets::compute_facets(
+            matches.iter().filter_map(|(key, _)| self.metadata.get(key)),
+            facet_fields,
+            facets::MAX_FACET_VALUES,
+        );
+
+        matches.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let total = matches.len();
+        matches.truncate(limit);
+
+        let results = matches
+            .into_iter()
+            .map(|(key, score)| SearchResult {
+                metadata: self.metadata.get(&key).cloned(),
+                positions: parsed.as_ref().map_or_else(Vec::new, |q| highlight_positions(&key, q)),
+                key,
+                score,
+            })
+            .collect();
+
+        let duration = start.elapsed();
+        if let Ok(metrics) = self.metrics.write() {
+            metrics.record_search(duration);
         }
+
+        Ok(FacetedResults { results, facets, total })
     }
-}
 
-#[no_mangle]
-pub extern "C" fn fabric_search(
-    query: *const c_char,
-    result: *mut *mut c_char,
-) -> bool {
-    unsafe {
-        if GLOBAL_INDEX.is_none() {
-            return false;
+    fn fuzzy_matches(&self, term: &str) -> Vec<(String, f32)> {
+        match self.config.matcher {
+            MatcherKind::Subsequence => self
+                .data
+                .keys()
+                .filter_map(|key| fuzzy_match(key, term).map(|score| (key.clone(), score)))
+                .collect(),
+            MatcherKind::Typo => self.typo_matches(term),
         }
-        
-        let query_cstr = CStr::from_ptr(query);
-        let query_str = match query_cstr.to_str() {
-            Ok(s) => s,
-            Err(_) => return false,
+    }
+
+    fn typo_matches(&self, term: &str) -> Vec<(String, f32)> {
+        let mut matches: HashMap<String, f32> = HashMap::new();
+
+        for word in tokenize(term) {
+            let max_distance = self.typo_distance(&word);
+            let automaton = LevenshteinAutomaton::new(&word, max_distance);
+            for (candidate, distance) in automaton.search_sorted(self.key_index.terms()) {
+                for key in self.key_index.documents_with_term(candidate) {
+                    let entry = matches.entry(key.to_string()).or_insert(0.0);
+                    *entry = entry.max(typo_weight(distance, max_distance));
+                }
+            }
+        }
+
+        for word in self.inverted_index.analyze(term) {
+            let max_distance = self.typo_distance(&word);
+            let automaton = LevenshteinAutomaton::new(&word, max_distance);
+            let mut content = Vec::new();
+            for (candidate, distance) in automaton.search_sorted(self.inverted_index.terms()) {
+                for (key, score) in self.inverted_index.search_term(candidate) {
+                    content.push((key, score * typo_weight(distance, max_distance)));
+                }
+            }
+            hybrid::normalize_scores(&mut content);
+            for (key, score) in content {
+                let entry = matches.entry(key).or_insert(0.0);
+                *entry = entry.max(score);
+            }
+        }
+
+        matches.into_iter().collect()
+    }
+
+    fn typo_distance(&self, word: &str) -> usize {
+        let max_distance = match word.chars().count() {
+            0..=2 => 0,
+            3..=5 => 1,
+            _ => 2,
         };
-        
-        if let Ok(index) = GLOBAL_INDEX.as_ref().unwrap().read() {
-            let results = index.search(query_str, 10);
-            if !results.is_empty() {
-                if let Ok(cstring) = CString::new(results[0].key.clone()) {
-                    *result = cstring.into_raw();
-                    return true;
+        max_distance.min(self.config.max_edit_distance)
+    }
+
+    fn evaluate(&self, query: &Query, texts: &HashMap<String, String>) -> HashMap<String, f32> {
+        match query {
+            Query::Term(term) => {
+                let mut matches: HashMap<String, f32> =
+                    self.inverted_index.search(term, usize::MAX).into_iter().collect();
+                if self.config.enable_fuzzy {
+                    for (key, score) in self.fuzzy_matches(term) {
+                        let entry = matches.entry(key).or_insert(0.0);
+                        *entry = entry.max(score);
+                    }
+                }
+                matches
+            }
+            Query::Phrase(words) => {
+                let phrase = words.join(" ");
+                let terms = self.inverted_index.analyze(&phrase);
+                let scores: HashMap<String, f32> =
+                    self.inverted_index.search(&phrase, usize::MAX).into_iter().collect();
+
+                let mut matches = HashMap::new();
+                if let Some(first) = terms.first() {
+                    for key in self.inverted_index.documents_with_term(first) {
+                        let text = self.document_text(key).or_else(|| texts.get(key).map(String::as_str));
+                        let contains_phrase = |text: &str| {
+                            self.inverted_index
+                                .analyze(text)
+                                .windows(terms.len())
+                                .any(|w| w == &terms[..])
+                        };
+                        if text.is_some_and(contains_phrase) {
+                            matches.insert(key.to_string(), scores.get(key).copied().unwrap_or(1.0));
+                        }
+                    }
+                }
+                for key in self.data.keys() {
+                    if tokenize(key).windows(words.len()).any(|w| w == &words[..]) {
+                        *matches.entry(key.clone()).or_insert(0.0) += 1.0;
+                    }
+                }
+                matches
+            }
+            Query::Prefix(prefix) => {
+                let mut matches = HashMap::new();
+                for term in self.inverted_index.terms_with_prefix(&self.inverted_index.normalize(prefix)) {
+                    for key in self.inverted_index.documents_with_term(term) {
+                        matches.insert(key.to_string(), 1.0);
+                    }
+                }
+                for key in self.data.keys() {
+                    if tokenize(key).iter().any(|token| token.starts_with(prefix.as_str())) {
+                        matches.insert(key.clone(), 1.0);
+                    }
+                }
+                matches
+            }
+            Query::Field { field, value } => self
+                .metadata
+                .iter()
+                .filter(|(_, meta)| {
+                    meta.get(field).is_some_and(|v| match self.field_analyzers.get(field) {
+                        Some(analyzer) => value.matches_analyzed(v, analyzer),
+                        None => value.matches(v),
+                    })
+                })
+                .map(|(key, _)| (key.clone(), 1.0))
+                .collect(),
+            Query::And(clauses) => {
+                let (negative, positive): (Vec<&Query>, Vec<&Query>) =
+                    clauses.iter().partition(|clause| matches!(clause, Query::Not(_)));
+
+                let mut matches: HashMap<String, f32> = match positive.split_first() {
+                    Some((first, _)) => self.evaluate(first, texts),
+                    None => self.data.keys().map(|key| (key.clone(), 0.0)).collect(),
+                };
+                for clause in positive.iter().skip(1) {
+                    let other = self.evaluate(clause, texts);
+                    matches.retain(|key, _| other.contains_key(key));
+                    for (key, score) in matches.iter_mut() {
+                        *score += other[key];
+                    }
                 }
+                for clause in negative {
+                    if let Query::Not(inner) = clause {
+                        let excluded = self.evaluate(inner, texts);
+                        matches.retain(|key, _| !excluded.contains_key(key));
+                    }
+                }
+                matches
+            }
+            Query::Or(clauses) => {
+                let mut matches = HashMap::new();
+                for clause in clauses {
+                    for (key, score) in self.evaluate(clause, texts) {
+                        *matches.entry(key).or_insert(0.0) += score;
+                    }
+                }
+                matches
+            }
+            Query::Not(inner) => {
+                let excluded = self.evaluate(inner, texts);
+                self.data
+                    .keys()
+                    .filter(|key| !excluded.contains_key(*key))
+                    .map(|key| (key.clone(), 0.0))
+                    .collect()
             }
         }
-        
-        false
     }
+
+    pub fn index_vector(&mut self, key: &str, vector: &[f32]) -> Result<(), String> {
+        if vector.is_empty() {
+            return Err("Vector must not be empty".to_string());
+        }
+
+        self.vector_index
+            .get_or_insert_with(|| VectorIndex::new(vector.len()))
+            .insert(key, vector)
+    }
+
+    pub fn remove_vector(&mut self, key: &str) -> bool {
+        match self.vector_index.as_mut() {
+            Some(index) => index.remove(key),
+            None => false,
+        }
+    }
+
+    pub fn set_vector_recall(&mut self, ef_search: usize) {
+        if let Some(index) = self.vector_index.as_mut() {
+            index.set_ef_search(ef_search);
+        }
+    }
+
+    pub fn vector_search(&self, query: &[f32], k: usize) -> Option<Vec<VectorSearchResult>> {
+        self.vector_index
+            .as_ref()
+            .filter(|index| index.dimensions() == query.len())
+            .map(|index| {
+                index
+                    .search(query, k)
+                    .into_iter()
+                    .map(|(key, score)| VectorSearchResult { key, score })
+                    .collect()
+            })
+    }
+}
+
+pub struct SearchResult {
+    pub key: String,
+    pub score: f32,
+    pub metadata: Option<HashMap<String, String>>,
+    pub positions: Vec<usize>,
+}
+
+fn suggestion_terms(key: &str, text: Option<&str>) -> HashSet<String> {
+    let mut terms: HashSet<String> = tokenize(key).into_iter().collect();
+    if let Some(text) = text {
+        terms.extend(tokenize(text));
+    }
+    terms.insert(key.to_string());
+    terms
+}
+
+fn typo_weight(distance: usize, max_distance: usize) -> f32 {
+    1.0 - distance as f32 / (max_distance + 1) as f32
+}
+
+fn highlight_positions(key: &str, query: &Query) -> Vec<usize> {
+    let mut positions = Vec::new();
+    collect_positions(key, query, &mut positions);
+    positions.sort_unstable();
+    positions.dedup();
+    positions
+}
+
+fn collect_positions(key: &str, query: &Query, positions: &mut Vec<usize>) {
+    match query {
+        Query::Term(term) | Query::Prefix(term) => {
+            if let Some((_, matched)) = fuzzy_match_positions(key, term) {
+                positions.extend(matched);
+            }
+        }
+        Query::Phrase(words) => {
+            for word in words {
+                if let Some((_, matched)) = fuzzy_match_positions(key, word) {
+                    positions.extend(matched);
+                }
+            }
+        }
+        Query::And(clauses) | Query::Or(clauses) => {
+            for clause in clauses {
+                collect_positions(key, clause, positions);
+            }
+        }
+        Query::Field { .. } | Query::Not(_) => {}
+    }
+}
+
+pub struct FacetedResults {
+    pub results: Vec<SearchResult>,
+    pub facets: HashMap<String, Vec<FacetCount>>,
+    pub total: usize,
+}
+
+pub struct VectorSearchResult {
+    pub key: String,
+    pub score: f32,
 }
 
-#[no_mangle]
-pub extern "C" fn fabric_free_string(s: *mut c_char) {
-    unsafe {
-        if !s.is_null() {
-            CString::from_raw(s);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_index(configure: impl FnOnce(&mut SearchConfig)) -> SearchIndex {
+        let mut config = Config::default().search;
+        config.min_score = 0.0;
+        configure(&mut config);
+
+        let mut index = SearchIndex::with_config(config);
+        for (key, text, vector) in [
+            ("alpha.txt", "solar panels and batteries", [1.0, 0.0]),
+            ("beta.txt", "wind turbines", [0.0, 1.0]),
+            ("gamma.txt", "tidal energy", [0.7, 0.7]),
+        ] {
+            index.index_data(key, text.as_bytes(), None);
+            index.index_vector(key, &vector).unwrap();
+        }
+        index
+    }
+
+    fn keys(results: &[SearchResult]) -> Vec<&str> {
+        results.iter().map(|result| result.key.as_str()).collect()
+    }
+
+    #[test]
+    fn test_released_documents_match_phrases_from_supplied_texts() {
+        let mut index = SearchIndex::new();
+        index.index_data("notes.txt", b"meeting notes", None);
+        index.index_data("todo.txt", b"notes for the meeting", None);
+        assert!(index.release_document("notes.txt"));
+        assert!(index.document_text("notes.txt").is_none());
+        assert_eq!(index.document_text("todo.txt"), Some("notes for the meeting"));
+
+        let phrase = "\"meeting notes\"";
+        assert!(index.query(phrase, 10).unwrap().is_empty());
+        assert_eq!(index.phrase_candidates(&parse_query(phrase).unwrap()), vec!["notes.txt"]);
+        let texts = HashMap::from([("notes.txt".to_string(), "meeting notes".to_string())]);
+        assert_eq!(keys(&index.query_with_texts(phrase, 10, &texts).unwrap()), vec!["notes.txt"]);
+
+        assert!(index.release_payload("todo.txt"));
+        assert_eq!(index.document_text("todo.txt"), Some("notes for the meeting"));
+        assert!(index.remove("notes.txt"));
+        assert!(index.remove("todo.txt"));
+        assert!(index.autocomplete("mee", 5).is_empty());
+    }
+
+    #[test]
+    fn test_hybrid_search_honors_fuzzy_and_vector_switches() {
+        let fusion = FusionMethod::ReciprocalRank { k: 60.0 };
+
+        let index = sample_index(|_| {});
+        assert_eq!(keys(&index.hybrid_search("bta", None, 10, fusion)), vec!["beta.txt"]);
+        let both = index.hybrid_search("turbines", Some(&[1.0, 0.0]), 10, fusion);
+        assert!(keys(&both).contains(&"beta.txt"));
+        assert!(keys(&both).contains(&"alpha.txt"));
+
+        let index = sample_index(|config| config.enable_fuzzy = false);
+        assert!(index.hybrid_search("bta", None, 10, fusion).is_empty());
+
+        let index = sample_index(|config| config.enable_vector = false);
+        let lexical = index.hybrid_search("turbines", Some(&[1.0, 0.0]), 10, fusion);
+        assert_eq!(keys(&lexical), vec!["beta.txt"]);
+    }
+
+    fn suggestions(index: &SearchIndex, prefix: &str) -> Vec<String> {
+        index.autocomplete(prefix, 10).into_iter().map(|s| s.text).collect()
+    }
+
+    #[test]
+    fn test_remove_clears_every_structure() {
+        let mut index = sample_index(|_| {});
+        assert!(index.remove("beta.txt"));
+        assert!(!index.remove("beta.txt"));
+
+        assert!(!index.contains("beta.txt"));
+        assert!(index.search_content("turbines", 10).is_empty());
+        assert!(index.search("beta", 10).is_empty());
+        assert!(suggestions(&index, "turb").is_empty());
+        let nearest = index.vector_search(&[0.0, 1.0], 3).unwrap();
+        assert!(nearest.iter().all(|result| result.key != "beta.txt"));
+        assert_eq!(nearest.len(), 2);
+    }
+
+    #[test]
+    fn test_update_replaces_body_and_merges_metadata() {
+        let mut index = sample_index(|_| {});
+        index.update("beta.txt", None, Some(metadata(&[("type", "txt")])));
+        assert!(index.update("beta.txt", Some(b"offshore hydro dams"), Some(metadata(&[("owner", "ops")]))));
+        assert!(!index.update("missing.txt", Some(b"hydro"), None));
+
+        assert!(index.search_content("turbines", 10).is_empty());
+        assert_eq!(keys(&index.search_content("hydro", 10)), vec!["beta.txt"]);
+        assert!(suggestions(&index, "turb").is_empty());
+        assert_eq!(suggestions(&index, "offs"), vec!["offshore"]);
+        let meta = index.get_metadata("beta.txt").unwrap();
+        assert_eq!(meta.get("type").map(String::as_str), Some("txt"));
+        assert_eq!(meta.get("owner").map(String::as_str), Some("ops"));
+        assert_eq!(index.vector_search(&[0.0, 1.0], 1).unwrap()[0].key, "beta.txt");
+        assert!(!index.contains("missing.txt"));
+    }
+
+    fn metadata(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_search_faceted_counts_filtered_matches() {
+        let mut index = SearchIndex::new();
+        for (key, text, type_, folder, size) in [
+            ("q1.pdf", "quarterly report", "pdf", "reports", "120"),
+            ("q2.docx", "quarterly report draft", "docx", "reports", "80"),
+            ("q3.pdf", "quarterly report", "pdf", "inbox", "300"),
+            ("notes.txt", "meeting notes", "txt", "reports", "5"),
+            ("q4.pdf", "annual report", "pdf", "reports", "500"),
+        ] {
+            let meta = metadata(&[("type", type_), ("folder", folder), ("size", size)]);
+            index.index_data(key, text.as_bytes(), Some(meta));
         }
+
+        let filters = [MetadataFilter::equals("folder", "reports")];
+        let faceted = index.search_faceted("report", &filters, &["type", "folder"], 1).unwrap();
+        assert_eq!(faceted.total, 3);
+        assert_eq!(faceted.results.len(), 1);
+        assert_eq!(
+            faceted.facets["type"],
+            vec![
+                FacetCount { value: "pdf".to_string(), count: 2 },
+                FacetCount { value: "docx".to_string(), count: 1 },
+            ]
+        );
+        assert_eq!(faceted.facets["folder"], vec![FacetCount { value: "reports".to_string(), count: 3 }]);
+
+        let filters = [
+            MetadataFilter::prefix("type", "pd"),
+            MetadataFilter::range("size", Some(100.0), Some(400.0)),
+        ];
+        let faceted = index.search_faceted("quarterly", &filters, &["folder"], 10).unwrap();
+        let mut matched = keys(&faceted.results);
+        matched.sort_unstable();
+        assert_eq!(matched, vec!["q1.pdf", "q3.pdf"]);
+        assert_eq!(faceted.total, 2);
+        assert_eq!(faceted.facets["folder"].len(), 2);
+
+        let everything = index.search_faceted("", &[], &["type"], 10).unwrap();
+        assert_eq!(everything.total, 5);
+        assert_eq!(everything.facets["type"][0], FacetCount { value: "pdf".to_string(), count: 3 });
+        assert!(index.search_faceted("(report", &[], &["type"], 10).is_err());
+    }
+
+    #[test]
+    fn test_hybrid_search_applies_min_score() {
+        let fusion = FusionMethod::Linear { lexical_weight: 0.5, vector_weight: 0.5 };
+
+        let index = sample_index(|_| {});
+        assert_eq!(index.hybrid_search("zzz", Some(&[1.0, 0.0]), 10, fusion).len(), 3);
+
+        let index = sample_index(|config| config.min_score = 0.9);
+        let results = index.hybrid_search("zzz", Some(&[1.0, 0.0]), 10, fusion);
+        assert_eq!(keys(&results), vec!["alpha.txt"]);
     }
 }
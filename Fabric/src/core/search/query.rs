@@ -0,0 +1,499 @@
+use super::analyzer::Analyzer;
+
+const MAX_QUERY_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    Term(String),
+    Phrase(Vec<String>),
+    Prefix(String),
+    Field { field: String, value: FieldValue },
+    And(Vec<Query>),
+    Or(Vec<Query>),
+    Not(Box<Query>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    Exact(String),
+    Phrase(String),
+    Prefix(String),
+    Range {
+        lower: Option<f64>,
+        upper: Option<f64>,
+        include_lower: bool,
+        include_upper: bool,
+    },
+}
+
+impl FieldValue {
+    pub fn matches(&self, value: &str) -> bool {
+        match self {
+            FieldValue::Exact(expected) => value.to_lowercase() == expected.to_lowercase(),
+            FieldValue::Phrase(expected) => value.to_lowercase().contains(&expected.to_lowercase()),
+            FieldValue::Prefix(prefix) => value.to_lowercase().starts_with(&prefix.to_lowercase()),
+            FieldValue::Range {
+                lower,
+                upper,
+                include_lower,
+                include_upper,
+            } => {
+                let value = match parse_range_value(value) {
+                    Some(value) => value,
+                    None => return false,
+                };
+                let above = match lower {
+                    Some(lower) if *include_lower => value >= *lower,
+                    Some(lower) => value > *lower,
+                    None => true,
+                };
+                let below = match upper {
+                    Some(upper) if *include_upper => value <= *upper,
+                    Some(upper) => value < *upper,
+                    None => true,
+                };
+                above && below
+            }
+        }
+    }
+
+    pub fn matches_analyzed(&self, value: &str, analyzer: &dyn Analyzer) -> bool {
+        match self {
+            FieldValue::Exact(expected) => analyzer.analyze(value) == analyzer.analyze(expected),
+            FieldValue::Phrase(expected) => {
+                let expected = analyzer.analyze(expected);
+                !expected.is_empty() && analyzer.analyze(value).windows(expected.len()).any(|w| w == &expected[..])
+            }
+            FieldValue::Prefix(prefix) => {
+                let prefix = analyzer.normalize(prefix);
+                analyzer.analyze(value).iter().any(|token| token.starts_with(&prefix))
+            }
+            FieldValue::Range { .. } => self.matches(value),
+        }
+    }
+}
+
+pub fn parse_query(input: &str) -> Result<Query, String> {
+    let mut parser = Parser {
+        chars: input.chars().collect(),
+        pos: 0,
+        depth: 0,
+    };
+
+    parser.skip_whitespace();
+    if parser.at_end() {
+        return Err("Empty query".to_string());
+    }
+
+    let query = parser.parse_or()?;
+    parser.skip_whitespace();
+    if !parser.at_end() {
+        return Err(format!("Unexpected '{}' at position {}", parser.chars[parser.pos], parser.pos));
+    }
+    Ok(query)
+}
+
+pub fn parse_range_value(value: &str) -> Option<f64> {
+    let value = value.trim();
+    if let Ok(number) = value.parse::<f64>() {
+        return Some(number).filter(|number| number.is_finite());
+    }
+    parse_date(value).map(|secs| secs as f64)
+}
+
+fn parse_date(value: &str) -> Option<i64> {
+    let (date, time) = match value.find(['T', ' ']) {
+        Some(idx) => (&value[..idx], Some(value[idx + 1..].trim_end_matches('Z'))),
+        None => (value, None),
+    };
+
+    let mut parts = date.splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: u32 = parts.next()?.parse().ok()?;
+    let day: u32 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+        return None;
+    }
+
+    let mut seconds = days_from_civil(year, month, day) * 86_400;
+    if let Some(time) = time {
+        let mut parts = time.splitn(3, ':');
+        let hours: i64 = parts.next()?.parse().ok()?;
+        let minutes: i64 = parts.next().unwrap_or("0").parse().ok()?;
+        let secs: f64 = parts.next().unwrap_or("0").parse().ok()?;
+        seconds += hours * 3600 + minutes * 60 + secs as i64;
+    }
+    Some(seconds)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let yoe = year - era * 400;
+    let month = month as i64;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn at_end(&self) -> bool {
+        self.pos >= self.chars.len()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(format!("Expected '{}' at position {}", expected, self.pos))
+        }
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        let len = keyword.chars().count();
+        if self.pos + len > self.chars.len() {
+            return false;
+        }
+        let word: String = self.chars[self.pos..self.pos + len].iter().collect();
+        let boundary = self
+            .chars
+            .get(self.pos + len)
+            .is_none_or(|c| c.is_whitespace() || *c == '(' || *c == '"');
+        word == keyword && boundary
+    }
+
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> Result<T, String>) -> Result<T, String> {
+        if self.depth >= MAX_QUERY_DEPTH {
+            return Err(format!("Query nests deeper than {} levels at position {}", MAX_QUERY_DEPTH, self.pos));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn parse_or(&mut self) -> Result<Query, String> {
+        let mut clauses = vec![self.parse_and()?];
+        loop {
+            self.skip_whitespace();
+            if self.peek_keyword("OR") {
+                self.pos += 2;
+                clauses.push(self.parse_and()?);
+            } else {
+                break;
+            }
+        }
+        Ok(flatten(clauses, Query::Or))
+    }
+
+    fn parse_and(&mut self) -> Result<Query, String> {
+        let mut clauses = vec![self.parse_unary()?];
+        loop {
+            self.skip_whitespace();
+            if self.at_end() || self.peek() == Some(')') || self.peek_keyword("OR") {
+                break;
+            }
+            if self.peek_keyword("AND") {
+                self.pos += 3;
+            }
+            clauses.push(self.parse_unary()?);
+        }
+        Ok(flatten(clauses, Query::And))
+    }
+
+    fn parse_unary(&mut self) -> Result<Query, String> {
+        self.skip_whitespace();
+        if self.peek_keyword("NOT") {
+            self.pos += 3;
+            return Ok(Query::Not(Box::new(self.nested(Self::parse_unary)?)));
+        }
+        if self.peek() == Some('-') {
+            self.pos += 1;
+            return Ok(Query::Not(Box::new(self.nested(Self::parse_unary)?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Query, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            None => Err("Unexpected end of query".to_string()),
+            Some('(') => {
+                self.pos += 1;
+                let query = self.nested(Self::parse_or)?;
+                self.skip_whitespace();
+                self.expect(')')?;
+                Ok(query)
+            }
+            Some('"') => {
+                let phrase = self.parse_quoted()?;
+                let words = super::inverted::tokenize(&phrase);
+                if words.is_empty() {
+                    return Err("Empty phrase".to_string());
+                }
+                Ok(Query::Phrase(words))
+            }
+            Some(_) => {
+                let start = self.pos;
+                let field = self.parse_field_name();
+                if !field.is_empty() && self.peek() == Some(':') {
+                    self.pos += 1;
+                    let value = self.parse_field_value()?;
+                    return Ok(Query::Field { field, value });
+                }
+
+                self.pos = start;
+                let word = self.parse_word();
+                if word.is_empty() {
+                    return Err(format!("Unexpected '{}' at position {}", self.chars[self.pos], self.pos));
+                }
+                match word.strip_suffix('*') {
+                    Some(prefix) if !prefix.is_empty() => Ok(Query::Prefix(prefix.to_lowercase())),
+                    _ => Ok(Query::Term(word.to_lowercase())),
+                }
+            }
+        }
+    }
+
+    fn parse_field_name(&mut self) -> String {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '.' || c == '-')
+        {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn parse_field_value(&mut self) -> Result<FieldValue, String> {
+        match self.peek() {
+            Some('"') => Ok(FieldValue::Phrase(self.parse_quoted()?)),
+            Some('[') | Some('{') => self.parse_range(),
+            Some('>') | Some('<') => self.parse_comparison(),
+            _ => {
+                let word = self.parse_word();
+                if word.is_empty() {
+                    return Err(format!("Missing field value at position {}", self.pos));
+                }
+                match word.strip_suffix('*') {
+                    Some(prefix) => Ok(FieldValue::Prefix(prefix.to_string())),
+                    None => Ok(FieldValue::Exact(word)),
+                }
+            }
+        }
+    }
+
+    fn parse_range(&mut self) -> Result<FieldValue, String> {
+        let include_lower = self.peek() == Some('[');
+        self.pos += 1;
+        self.skip_whitespace();
+        let lower = self.parse_bound()?;
+        self.skip_whitespace();
+        if !self.peek_keyword("TO") {
+            return Err(format!("Expected 'TO' at position {}", self.pos));
+        }
+        self.pos += 2;
+        self.skip_whitespace();
+        let upper = self.parse_bound()?;
+        self.skip_whitespace();
+
+        let include_upper = match self.peek() {
+            Some(']') => true,
+            Some('}') => false,
+            _ => return Err(format!("Unterminated range at position {}", self.pos)),
+        };
+        self.pos += 1;
+
+        Ok(FieldValue::Range {
+            lower,
+            upper,
+            include_lower,
+            include_upper,
+        })
+    }
+
+    fn parse_bound(&mut self) -> Result<Option<f64>, String> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| !c.is_whitespace() && c != ']' && c != '}')
+        {
+            self.pos += 1;
+        }
+        let bound: String = self.chars[start..self.pos].iter().collect();
+        if bound == "*" {
+            return Ok(None);
+        }
+        parse_range_value(&bound)
+            .map(Some)
+            .ok_or_else(|| format!("Invalid range bound '{}'", bound))
+    }
+
+    fn parse_comparison(&mut self) -> Result<FieldValue, String> {
+        let greater = self.peek() == Some('>');
+        self.pos += 1;
+        let inclusive = self.peek() == Some('=');
+        if inclusive {
+            self.pos += 1;
+        }
+
+        let word = self.parse_word();
+        let bound = parse_range_value(&word).ok_or_else(|| format!("Invalid range bound '{}'", word))?;
+        Ok(if greater {
+            FieldValue::Range {
+                lower: Some(bound),
+                upper: None,
+                include_lower: inclusive,
+                include_upper: false,
+            }
+        } else {
+            FieldValue::Range {
+                lower: None,
+                upper: Some(bound),
+                include_lower: false,
+                include_upper: inclusive,
+            }
+        })
+    }
+
+    fn parse_quoted(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                None => return Err("Unterminated quote".to_string()),
+                Some('"') => {
+                    self.pos += 1;
+                    return Ok(value);
+                }
+                Some('\\') if self.chars.get(self.pos + 1).is_some() => {
+                    value.push(self.chars[self.pos + 1]);
+                    self.pos += 2;
+                }
+                Some(c) => {
+                    value.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    fn parse_word(&mut self) -> String {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| !c.is_whitespace() && c != '(' && c != ')' && c != '"')
+        {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+}
+
+fn flatten(mut clauses: Vec<Query>, combine: fn(Vec<Query>) -> Query) -> Query {
+    if clauses.len() == 1 {
+        clauses.remove(0)
+    } else {
+        combine(clauses)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_boolean_and_phrases() {
+        let query = parse_query("report \"quarterly sales\" OR NOT draft").unwrap();
+        assert_eq!(
+            query,
+            Query::Or(vec![
+                Query::And(vec![
+                    Query::Term("report".to_string()),
+                    Query::Phrase(vec!["quarterly".to_string(), "sales".to_string()]),
+                ]),
+                Query::Not(Box::new(Query::Term("draft".to_string()))),
+            ])
+        );
+
+        let query = parse_query("(a OR b) AND -c*").unwrap();
+        assert_eq!(
+            query,
+            Query::And(vec![
+                Query::Or(vec![Query::Term("a".to_string()), Query::Term("b".to_string())]),
+                Query::Not(Box::new(Query::Prefix("c".to_string()))),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_fields_and_ranges() {
+        let query = parse_query("type:pdf author:\"jo\" size:[10 TO *] created:>=2024-01-01").unwrap();
+        let clauses = match query {
+            Query::And(clauses) => clauses,
+            other => panic!("Unexpected query {:?}", other),
+        };
+        assert_eq!(clauses.len(), 4);
+
+        let created = match &clauses[3] {
+            Query::Field { value, .. } => value.clone(),
+            other => panic!("Unexpected clause {:?}", other),
+        };
+        assert!(created.matches("2024-03-05"));
+        assert!(!created.matches("2023-12-31"));
+
+        let size = match &clauses[2] {
+            Query::Field { value, .. } => value.clone(),
+            other => panic!("Unexpected clause {:?}", other),
+        };
+        assert!(size.matches("10"));
+        assert!(!size.matches("9.5"));
+
+        assert!(parse_query("size:[1 TO").is_err());
+        assert!(parse_query("\"open").is_err());
+        assert!(parse_query("(a b").is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_deep_nesting_and_invalid_bounds() {
+        let shallow = format!("{}a{}", "(".repeat(MAX_QUERY_DEPTH), ")".repeat(MAX_QUERY_DEPTH));
+        assert_eq!(parse_query(&shallow).unwrap(), Query::Term("a".to_string()));
+        assert!(parse_query(&"(".repeat(100_000)).is_err());
+        assert!(parse_query(&"NOT ".repeat(100_000)).is_err());
+        assert!(parse_query(&"-".repeat(100_000)).is_err());
+
+        assert!(parse_query("size:[NaN TO 5]").is_err());
+        assert!(parse_query("size:<inf").is_err());
+        assert!(parse_query("created:>2023-02-31").is_err());
+        assert!(parse_query("created:>2024-02-29").is_ok());
+        assert!(parse_query("created:>2023-02-29").is_err());
+        assert!(parse_range_value("2024-04-31").is_none());
+    }
+}
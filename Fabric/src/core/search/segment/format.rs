@@ -0,0 +1,453 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use memmap2::Mmap;
+
+pub const FORMAT_VERSION: u32 = 2;
+
+const HEADER_MAGIC: &[u8; 4] = b"FSEG";
+const FOOTER_MAGIC: &[u8; 4] = b"FEND";
+const HEADER_LEN: usize = 16;
+const FOOTER_LEN: usize = 4 * 8 + 4 + 4 + 4;
+const NO_TEXT: u32 = u32::MAX;
+const FLAG_DELETED: u8 = 1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredDocument {
+    pub key: String,
+    pub deleted: bool,
+    pub text: Option<String>,
+    pub metadata: HashMap<String, String>,
+    pub vector: Option<Vec<f32>>,
+    pub checksum: u32,
+    pub length: u32,
+    pub terms: Vec<(String, u32)>,
+}
+
+impl StoredDocument {
+    pub fn tombstone(key: &str) -> Self {
+        StoredDocument {
+            key: key.to_string(),
+            deleted: true,
+            text: None,
+            metadata: HashMap::new(),
+            vector: None,
+            checksum: 0,
+            length: 0,
+            terms: Vec::new(),
+        }
+    }
+}
+
+pub fn write_segment(path: &Path, documents: &[StoredDocument]) -> Result<(), String> {
+    let mut postings: BTreeMap<&str, Vec<(u32, u32)>> = BTreeMap::new();
+    for (ordinal, document) in documents.iter().enumerate().filter(|(_, document)| !document.deleted) {
+        for (term, tf) in &document.terms {
+            postings.entry(term).or_default().push((ordinal as u32, *tf));
+        }
+    }
+
+    let mut out = SegmentBuffer::default();
+    out.bytes.extend_from_slice(HEADER_MAGIC);
+    out.u32(FORMAT_VERSION);
+    out.u32(0);
+    out.u32(0);
+
+    let docs_offset = out.position();
+    out.u32(documents.len() as u32);
+    let doc_table = out.reserve_table(documents.len());
+    for (ordinal, document) in documents.iter().enumerate() {
+        out.patch_table(doc_table, ordinal);
+        out.string(&document.key);
+        out.bytes.push(if document.deleted { FLAG_DELETED } else { 0 });
+        out.u32(document.checksum);
+        out.u32(document.length);
+        match &document.text {
+            Some(text) => out.string(text),
+            None => out.u32(NO_TEXT),
+        }
+        let metadata = serde_json::to_string(&document.metadata)
+            .map_err(|e| format!("Failed to serialize metadata: {}", e))?;
+        out.string(&metadata);
+    }
+
+    let terms_offset = out.position();
+    out.u32(postings.len() as u32);
+    let term_table = out.reserve_table(postings.len());
+    let mut postings_offset = 0u64;
+    for (index, (term, docs)) in postings.iter().enumerate() {
+        out.patch_table(term_table, index);
+        out.string(term);
+        out.u32(docs.len() as u32);
+        out.u64(postings_offset);
+        postings_offset += docs.len() as u64 * 8;
+    }
+
+    let postings_section = out.position();
+    for docs in postings.values() {
+        for (doc, tf) in docs {
+            out.u32(*doc);
+            out.u32(*tf);
+        }
+    }
+
+    let vectors_offset = out.position();
+    let vectors: Vec<(u32, &Vec<f32>)> = documents
+        .iter()
+        .enumerate()
+        .filter_map(|(ordinal, document)| document.vector.as_ref().map(|vector| (ordinal as u32, vector)))
+        .collect();
+    let dimensions = vectors.first().map_or(0, |(_, vector)| vector.len());
+    if vectors.iter().any(|(_, vector)| vector.len() != dimensions) {
+        return Err("Segment vectors must share dimensions".to_string());
+    }
+    out.u32(dimensions as u32);
+    out.u32(vectors.len() as u32);
+    for (ordinal, vector) in vectors {
+        out.u32(ordinal);
+        for value in vector {
+            out.bytes.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    let checksum = crc32fast::hash(&out.bytes);
+    out.u64(docs_offset);
+    out.u64(terms_offset);
+    out.u64(postings_section);
+    out.u64(vectors_offset);
+    out.u32(checksum);
+    out.u32(FORMAT_VERSION);
+    out.bytes.extend_from_slice(FOOTER_MAGIC);
+
+    write_durably(path, &out.bytes)
+}
+
+pub fn write_durably(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    let temp = path.with_extension("tmp");
+    {
+        let file = File::create(&temp).map_err(|e| format!("Failed to create {}: {}", temp.display(), e))?;
+        let mut writer = BufWriter::new(file);
+        writer
+            .write_all(bytes)
+            .map_err(|e| format!("Failed to write {}: {}", temp.display(), e))?;
+        writer
+            .into_inner()
+            .map_err(|e| format!("Failed to flush {}: {}", temp.display(), e))?
+            .sync_all()
+            .map_err(|e| format!("Failed to sync {}: {}", temp.display(), e))?;
+    }
+    fs::rename(&temp, path).map_err(|e| format!("Failed to publish {}: {}", path.display(), e))?;
+    sync_directory(path.parent().unwrap_or(Path::new(".")))
+}
+
+fn sync_directory(dir: &Path) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        File::open(dir)
+            .and_then(|dir| dir.sync_all())
+            .map_err(|e| format!("Failed to sync {}: {}", dir.display(), e))?;
+    }
+    #[cfg(not(unix))]
+    let _ = dir;
+    Ok(())
+}
+
+#[derive(Default)]
+struct SegmentBuffer {
+    bytes: Vec<u8>,
+}
+
+impl SegmentBuffer {
+    fn position(&self) -> u64 {
+        self.bytes.len() as u64
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn blob(&mut self, value: &[u8]) {
+        self.u32(value.len() as u32);
+        self.bytes.extend_from_slice(value);
+    }
+
+    fn string(&mut self, value: &str) {
+        self.blob(value.as_bytes());
+    }
+
+    fn reserve_table(&mut self, entries: usize) -> usize {
+        let start = self.bytes.len();
+        self.bytes.resize(start + entries * 8, 0);
+        start
+    }
+
+    fn patch_table(&mut self, table: usize, index: usize) {
+        let position = self.position().to_le_bytes();
+        self.bytes[table + index * 8..table + index * 8 + 8].copy_from_slice(&position);
+    }
+}
+
+pub struct SegmentReader {
+    mmap: Mmap,
+    doc_count: usize,
+    docs_table: usize,
+    term_count: usize,
+    terms_table: usize,
+    postings_offset: usize,
+    vectors_offset: usize,
+}
+
+impl SegmentReader {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("Failed to open segment {}: {}", path.display(), e))?;
+        let mmap = unsafe { Mmap::map(&file) }
+            .map_err(|e| format!("Failed to map segment {}: {}", path.display(), e))?;
+
+        if mmap.len() < HEADER_LEN + FOOTER_LEN || &mmap[..4] != HEADER_MAGIC || !mmap.ends_with(FOOTER_MAGIC) {
+            return Err(format!("{} is not a Fabric segment", path.display()));
+        }
+        let version = read_u32(&mmap, 4)?;
+        if version != FORMAT_VERSION {
+            return Err(format!(
+                "Segment {} uses format version {}, only version {} is supported",
+                path.display(),
+                version,
+                FORMAT_VERSION
+            ));
+        }
+
+        let footer = mmap.len() - FOOTER_LEN;
+        let checksum = read_u32(&mmap, footer + 32)?;
+        if crc32fast::hash(&mmap[..footer]) != checksum {
+            return Err(format!("Segment {} is corrupt (checksum mismatch)", path.display()));
+        }
+
+        let docs_offset = read_u64(&mmap, footer)? as usize;
+        let terms_offset = read_u64(&mmap, footer + 8)? as usize;
+        Ok(SegmentReader {
+            doc_count: read_u32(&mmap, docs_offset)? as usize,
+            docs_table: docs_offset + 4,
+            term_count: read_u32(&mmap, terms_offset)? as usize,
+            terms_table: terms_offset + 4,
+            postings_offset: read_u64(&mmap, footer + 16)? as usize,
+            vectors_offset: read_u64(&mmap, footer + 24)? as usize,
+            mmap,
+        })
+    }
+
+    pub fn doc_count(&self) -> usize {
+        self.doc_count
+    }
+
+    pub fn term_count(&self) -> usize {
+        self.term_count
+    }
+
+    pub fn size(&self) -> usize {
+        self.mmap.len()
+    }
+
+    pub fn document(&self, ordinal: usize) -> Result<StoredDocument, String> {
+        if ordinal >= self.doc_count {
+            return Err(format!("Document {} out of range", ordinal));
+        }
+        let mut cursor = read_u64(&self.mmap, self.docs_table + ordinal * 8)? as usize;
+        let key = read_string(&self.mmap, &mut cursor)?;
+        let deleted = *self.mmap.get(cursor).ok_or("Truncated document record")? & FLAG_DELETED != 0;
+        cursor += 1;
+        let checksum = read_u32(&self.mmap, cursor)?;
+        let length = read_u32(&self.mmap, cursor + 4)?;
+        cursor += 8;
+        let text = if read_u32(&self.mmap, cursor)? == NO_TEXT {
+            cursor += 4;
+            None
+        } else {
+            Some(read_string(&self.mmap, &mut cursor)?)
+        };
+        let metadata = serde_json::from_str(&read_string(&self.mmap, &mut cursor)?)
+            .map_err(|e| format!("Failed to parse stored metadata: {}", e))?;
+
+        Ok(StoredDocument {
+            key,
+            deleted,
+            text,
+            metadata,
+            vector: None,
+            checksum,
+            length,
+            terms: Vec::new(),
+        })
+    }
+
+    pub fn documents(&self) -> Result<Vec<StoredDocument>, String> {
+        let mut documents = (0..self.doc_count)
+            .map(|ordinal| self.document(ordinal))
+            .collect::<Result<Vec<_>, String>>()?;
+        for (ordinal, vector) in self.vectors()? {
+            if let Some(document) = documents.get_mut(ordinal as usize) {
+                document.vector = Some(vector);
+            }
+        }
+        for index in 0..self.term_count {
+            let (term, _) = self.term(index)?;
+            for (ordinal, tf) in self.term_postings(index)? {
+                if let Some(document) = documents.get_mut(ordinal as usize) {
+                    document.terms.push((term.clone(), tf));
+                }
+            }
+        }
+        Ok(documents)
+    }
+
+    pub fn term(&self, index: usize) -> Result<(String, u32), String> {
+        let (term, doc_freq, _) = self.read_term(index)?;
+        Ok((term, doc_freq))
+    }
+
+    fn read_term(&self, index: usize) -> Result<(String, u32, usize), String> {
+        let mut cursor = read_u64(&self.mmap, self.terms_table + index * 8)? as usize;
+        let term = read_string(&self.mmap, &mut cursor)?;
+        let doc_freq = read_u32(&self.mmap, cursor)?;
+        let offset = read_u64(&self.mmap, cursor + 4)? as usize;
+        Ok((term, doc_freq, offset))
+    }
+
+    pub fn find_term(&self, term: &str) -> Result<Option<usize>, String> {
+        let (mut low, mut high) = (0, self.term_count);
+        while low < high {
+            let mid = (low + high) / 2;
+            let (candidate, _) = self.term(mid)?;
+            match candidate.as_str().cmp(term) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+                std::cmp::Ordering::Equal => return Ok(Some(mid)),
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn postings(&self, term: &str) -> Result<Vec<(u32, u32)>, String> {
+        match self.find_term(term)? {
+            Some(index) => self.term_postings(index),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    pub fn term_postings(&self, index: usize) -> Result<Vec<(u32, u32)>, String> {
+        let (_, doc_freq, offset) = self.read_term(index)?;
+        let start = self.postings_offset + offset;
+        (0..doc_freq as usize)
+            .map(|i| {
+                let entry = start + i * 8;
+                Ok((read_u32(&self.mmap, entry)?, read_u32(&self.mmap, entry + 4)?))
+            })
+            .collect()
+    }
+
+    pub fn vectors(&self) -> Result<Vec<(u32, Vec<f32>)>, String> {
+        let dimensions = read_u32(&self.mmap, self.vectors_offset)? as usize;
+        let count = read_u32(&self.mmap, self.vectors_offset + 4)? as usize;
+        let mut cursor = self.vectors_offset + 8;
+        let mut vectors = Vec::with_capacity(count);
+        for _ in 0..count {
+            let ordinal = read_u32(&self.mmap, cursor)?;
+            cursor += 4;
+            let vector = (0..dimensions)
+                .map(|i| read_u32(&self.mmap, cursor + i * 4).map(f32::from_bits))
+                .collect::<Result<Vec<f32>, String>>()?;
+            cursor += dimensions * 4;
+            vectors.push((ordinal, vector));
+        }
+        Ok(vectors)
+    }
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, String> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| format!("Truncated segment at offset {}", offset))
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> Result<u64, String> {
+    bytes
+        .get(offset..offset + 8)
+        .map(|b| u64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]))
+        .ok_or_else(|| format!("Truncated segment at offset {}", offset))
+}
+
+fn read_blob<'a>(bytes: &'a [u8], cursor: &mut usize) -> Result<&'a [u8], String> {
+    let len = read_u32(bytes, *cursor)? as usize;
+    let start = *cursor + 4;
+    let blob = bytes
+        .get(start..start + len)
+        .ok_or_else(|| format!("Truncated segment at offset {}", start))?;
+    *cursor = start + len;
+    Ok(blob)
+}
+
+fn read_string(bytes: &[u8], cursor: &mut usize) -> Result<String, String> {
+    let blob = read_blob(bytes, cursor)?;
+    String::from_utf8(blob.to_vec()).map_err(|_| "Invalid UTF-8 in segment".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_segment_roundtrip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.fseg");
+        let documents = vec![
+            StoredDocument {
+                key: "a.txt".to_string(),
+                deleted: false,
+                text: Some("alpha beta beta".to_string()),
+                metadata: HashMap::from([("lang".to_string(), "en".to_string())]),
+                vector: Some(vec![0.5, -1.0]),
+                checksum: crc32fast::hash(b"alpha beta beta"),
+                length: 3,
+                terms: vec![("alpha".to_string(), 1), ("beta".to_string(), 2)],
+            },
+            StoredDocument::tombstone("b.txt"),
+            StoredDocument {
+                key: "c.bin".to_string(),
+                deleted: false,
+                text: None,
+                metadata: HashMap::new(),
+                vector: None,
+                checksum: crc32fast::hash(&[0, 1, 2]),
+                length: 0,
+                terms: Vec::new(),
+            },
+        ];
+
+        write_segment(&path, &documents).unwrap();
+
+        let reader = SegmentReader::open(&path).unwrap();
+        assert_eq!(reader.doc_count(), 3);
+        assert_eq!(reader.documents().unwrap(), documents);
+        assert_eq!(reader.term_count(), 2);
+        assert_eq!(reader.postings("beta").unwrap(), vec![(0, 2)]);
+        assert!(reader.postings("gamma").unwrap().is_empty());
+        drop(reader);
+
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[HEADER_LEN + 10] ^= 0xFF;
+        fs::write(&path, &bytes).unwrap();
+        assert!(SegmentReader::open(&path).err().unwrap().contains("checksum"));
+
+        bytes[HEADER_LEN + 10] ^= 0xFF;
+        bytes[4..8].copy_from_slice(&1u32.to_le_bytes());
+        fs::write(&path, &bytes).unwrap();
+        assert!(SegmentReader::open(&path).err().unwrap().contains("format version 1"));
+    }
+}
@@ -0,0 +1,461 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+mod format;
+
+pub use format::{SegmentReader, StoredDocument, FORMAT_VERSION};
+use format::{write_durably, write_segment};
+
+const MANIFEST_FILE: &str = "MANIFEST";
+const SEGMENT_EXTENSION: &str = "fseg";
+const MERGE_POLL_INTERVAL: Duration = Duration::from_secs(30);
+const MERGE_FACTOR: usize = 4;
+const MAX_MERGE_ATTEMPTS: u32 = 3;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    generation: u64,
+    next_segment: u64,
+    segments: Vec<String>,
+}
+
+struct Segment {
+    name: String,
+    reader: Arc<SegmentReader>,
+}
+
+struct StoreState {
+    manifest: Manifest,
+    segments: Vec<Segment>,
+}
+
+pub struct SegmentStore {
+    dir: PathBuf,
+    state: Mutex<StoreState>,
+    merging: Mutex<()>,
+}
+
+impl SegmentStore {
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, String> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create index directory: {}", e))?;
+
+        let manifest_path = dir.join(MANIFEST_FILE);
+        let manifest = if manifest_path.exists() {
+            let contents = fs::read_to_string(&manifest_path)
+                .map_err(|e| format!("Failed to read index manifest: {}", e))?;
+            serde_json::from_str::<Manifest>(&contents)
+                .map_err(|e| format!("Failed to parse index manifest: {}", e))?
+        } else {
+            Manifest {
+                version: FORMAT_VERSION,
+                ..Manifest::default()
+            }
+        };
+        if manifest.version > FORMAT_VERSION {
+            return Err(format!(
+                "Index format version {} is newer than supported version {}",
+                manifest.version, FORMAT_VERSION
+            ));
+        }
+        if manifest.version < FORMAT_VERSION && !manifest.segments.is_empty() {
+            return Err(format!(
+                "Index format version {} is no longer supported, remove {} to rebuild the index",
+                manifest.version,
+                dir.display()
+            ));
+        }
+
+        let segments = manifest
+            .segments
+            .iter()
+            .map(|name| {
+                Ok(Segment {
+                    name: name.clone(),
+                    reader: Arc::new(SegmentReader::open(&dir.join(name))?),
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let store = SegmentStore {
+            dir,
+            state: Mutex::new(StoreState { manifest, segments }),
+            merging: Mutex::new(()),
+        };
+        store.remove_unreferenced()?;
+        Ok(store)
+    }
+
+    fn state(&self) -> Result<MutexGuard<'_, StoreState>, String> {
+        self.state
+            .lock()
+            .map_err(|_| "Failed to acquire segment store lock".to_string())
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn generation(&self) -> Result<u64, String> {
+        Ok(self.state()?.manifest.generation)
+    }
+
+    pub fn segment_count(&self) -> Result<usize, String> {
+        Ok(self.state()?.segments.len())
+    }
+
+    pub fn readers(&self) -> Result<Vec<Arc<SegmentReader>>, String> {
+        Ok(self.state()?.segments.iter().map(|segment| segment.reader.clone()).collect())
+    }
+
+    pub fn commit(&self, mut documents: Vec<StoredDocument>) -> Result<(), String> {
+        if documents.is_empty() {
+            return Ok(());
+        }
+        documents.sort_by(|a, b| a.key.cmp(&b.key));
+        documents.dedup_by(|later, earlier| later.key == earlier.key);
+
+        let mut state = self.state()?;
+        let name = self.write_new_segment(&mut state.manifest, &documents)?;
+
+        let mut manifest = state.manifest.clone();
+        manifest.segments.push(name.clone());
+        self.publish(&mut state, manifest)?;
+        state.segments.push(Segment {
+            reader: Arc::new(SegmentReader::open(&self.dir.join(&name))?),
+            name,
+        });
+        Ok(())
+    }
+
+    pub fn documents(&self) -> Result<Vec<StoredDocument>, String> {
+        let readers = self.readers()?;
+        Ok(live_documents(&readers)?.into_values().collect())
+    }
+
+    pub fn merge(&self, width: usize) -> Result<bool, String> {
+        let _merging = self
+            .merging
+            .lock()
+            .map_err(|_| "Failed to acquire merge lock".to_string())?;
+
+        let (start, names, readers): (usize, Vec<String>, Vec<Arc<SegmentReader>>) = {
+            let state = self.state()?;
+            let width = width.clamp(2, state.segments.len().max(2));
+            if state.segments.len() < width {
+                return Ok(false);
+            }
+            let start = (0..=state.segments.len() - width)
+                .min_by_key(|&start| {
+                    state.segments[start..start + width]
+                        .iter()
+                        .map(|segment| segment.reader.size())
+                        .sum::<usize>()
+                })
+                .unwrap_or(0);
+            let (names, readers) = state.segments[start..start + width]
+                .iter()
+                .map(|segment| (segment.name.clone(), segment.reader.clone()))
+                .unzip();
+            (start, names, readers)
+        };
+
+        let documents: Vec<StoredDocument> = newest_documents(&readers, start > 0)?.into_values().collect();
+
+        let window = start..start + names.len();
+        let mut state = self.state()?;
+        let name = self.write_new_segment(&mut state.manifest, &documents)?;
+        let mut manifest = state.manifest.clone();
+        manifest.segments.splice(window.clone(), [name.clone()]);
+        self.publish(&mut state, manifest)?;
+
+        let reader = Arc::new(SegmentReader::open(&self.dir.join(&name))?);
+        state.segments.splice(window, [Segment { name, reader }]);
+        drop(state);
+
+        for name in names {
+            let _ = fs::remove_file(self.dir.join(name));
+        }
+        Ok(true)
+    }
+
+    fn write_new_segment(&self, manifest: &mut Manifest, documents: &[StoredDocument]) -> Result<String, String> {
+        let name = format!("{:012}.{}", manifest.next_segment, SEGMENT_EXTENSION);
+        manifest.next_segment += 1;
+        write_segment(&self.dir.join(&name), documents)?;
+        Ok(name)
+    }
+
+    fn publish(&self, state: &mut StoreState, mut manifest: Manifest) -> Result<(), String> {
+        manifest.version = FORMAT_VERSION;
+        manifest.generation += 1;
+        let contents = serde_json::to_vec_pretty(&manifest)
+            .map_err(|e| format!("Failed to serialize index manifest: {}", e))?;
+        write_durably(&self.dir.join(MANIFEST_FILE), &contents)?;
+        state.manifest = manifest;
+        Ok(())
+    }
+
+    fn remove_unreferenced(&self) -> Result<(), String> {
+        let referenced: HashSet<String> = self.state()?.manifest.segments.iter().cloned().collect();
+        let entries = fs::read_dir(&self.dir).map_err(|e| format!("Failed to list index directory: {}", e))?;
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let path = entry.path();
+            let extension = path.extension().and_then(|ext| ext.to_str());
+            let stale = match extension {
+                Some("tmp") => true,
+                Some(SEGMENT_EXTENSION) => !referenced.contains(&name),
+                _ => false,
+            };
+            if stale {
+                let _ = fs::remove_file(path);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn live_documents(readers: &[Arc<SegmentReader>]) -> Result<BTreeMap<String, StoredDocument>, String> {
+    newest_documents(readers, false)
+}
+
+fn newest_documents(
+    readers: &[Arc<SegmentReader>],
+    keep_tombstones: bool,
+) -> Result<BTreeMap<String, StoredDocument>, String> {
+    let mut documents = BTreeMap::new();
+    let mut deleted = HashSet::new();
+    for reader in readers.iter().rev() {
+        for document in reader.documents()? {
+            if documents.contains_key(&document.key) || deleted.contains(&document.key) {
+                continue;
+            }
+            if document.deleted {
+                deleted.insert(document.key.clone());
+                if keep_tombstones {
+                    documents.insert(document.key.clone(), document);
+                }
+            } else {
+                documents.insert(document.key.clone(), document);
+            }
+        }
+    }
+    Ok(documents)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergeStatus {
+    pub merges: u64,
+    pub failures: u64,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+}
+
+pub struct SegmentMerger {
+    sender: Option<Sender<()>>,
+    status: Arc<Mutex<MergeStatus>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl SegmentMerger {
+    pub fn spawn(store: Arc<SegmentStore>, max_segments: usize) -> Self {
+        Self::spawn_with_interval(store, max_segments, MERGE_POLL_INTERVAL)
+    }
+
+    fn spawn_with_interval(store: Arc<SegmentStore>, max_segments: usize, interval: Duration) -> Self {
+        let (sender, receiver) = mpsc::channel::<()>();
+        let status = Arc::new(Mutex::new(MergeStatus::default()));
+        let shared = status.clone();
+        let handle = thread::spawn(move || {
+            let mut attempts = 0;
+            loop {
+                match receiver.recv_timeout(interval) {
+                    Ok(()) => attempts = 0,
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+                let excess = match store.segment_count() {
+                    Ok(count) if count > max_segments => count - max_segments,
+                    _ => continue,
+                };
+                if attempts >= MAX_MERGE_ATTEMPTS {
+                    continue;
+                }
+                let result = store.merge(MERGE_FACTOR.max(excess + 1));
+                let Ok(mut status) = shared.lock() else {
+                    break;
+                };
+                match result {
+                    Ok(merged) => {
+                        attempts = 0;
+                        status.merges += merged as u64;
+                        status.consecutive_failures = 0;
+                    }
+                    Err(e) => {
+                        attempts += 1;
+                        status.failures += 1;
+                        status.consecutive_failures += 1;
+                        status.last_error = Some(e);
+                    }
+                }
+            }
+        });
+        SegmentMerger {
+            sender: Some(sender),
+            status,
+            handle: Some(handle),
+        }
+    }
+
+    pub fn status(&self) -> MergeStatus {
+        self.status.lock().map(|status| status.clone()).unwrap_or_default()
+    }
+
+    pub fn notify(&self) {
+        if let Some(sender) = &self.sender {
+            let _ = sender.send(());
+        }
+    }
+}
+
+impl Drop for SegmentMerger {
+    fn drop(&mut self) {
+        self.sender.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tempfile::tempdir;
+
+    fn document(key: &str, text: &str) -> StoredDocument {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        StoredDocument {
+            key: key.to_string(),
+            deleted: false,
+            text: Some(text.to_string()),
+            metadata: HashMap::new(),
+            vector: None,
+            checksum: crc32fast::hash(text.as_bytes()),
+            length: words.len() as u32,
+            terms: words.into_iter().map(|word| (word.to_string(), 1)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_commit_reopen_and_merge() {
+        let dir = tempdir().unwrap();
+
+        {
+            let store = SegmentStore::open(dir.path()).unwrap();
+            store.commit(vec![document("a", "alpha"), document("b", "beta")]).unwrap();
+            store.commit(vec![document("a", "alpha two"), StoredDocument::tombstone("b")]).unwrap();
+            store.commit(vec![document("c", "gamma alpha")]).unwrap();
+            assert_eq!(store.segment_count().unwrap(), 3);
+        }
+        fs::write(dir.path().join("000000000099.fseg"), b"partial write").unwrap();
+
+        let store = Arc::new(SegmentStore::open(dir.path()).unwrap());
+        assert!(!dir.path().join("000000000099.fseg").exists());
+        let keys = |store: &SegmentStore| -> Vec<(String, String)> {
+            store
+                .documents()
+                .unwrap()
+                .into_iter()
+                .map(|d| (d.key, d.text.unwrap()))
+                .collect()
+        };
+        let expected = vec![("a".to_string(), "alpha two".to_string()), ("c".to_string(), "gamma alpha".to_string())];
+        assert_eq!(keys(&store), expected);
+
+        let generation = store.generation().unwrap();
+        drop(SegmentMerger::spawn(store.clone(), 1));
+        assert!(store.merge(usize::MAX).unwrap() || store.segment_count().unwrap() == 1);
+        assert_eq!(store.segment_count().unwrap(), 1);
+        assert!(store.generation().unwrap() > generation);
+        assert_eq!(keys(&store), expected);
+
+        let reader = &store.readers().unwrap()[0];
+        assert_eq!(reader.postings("alpha").unwrap(), vec![(0, 1), (1, 1)]);
+        assert!(reader.postings("beta").unwrap().is_empty());
+        let merged = store.documents().unwrap();
+        assert_eq!(merged[0].terms, vec![("alpha".to_string(), 1), ("two".to_string(), 1)]);
+        assert_eq!(merged[1].length, 2);
+
+        let reopened = SegmentStore::open(dir.path()).unwrap();
+        assert_eq!(keys(&reopened), expected);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_merge_window_keeps_later_tombstones() {
+        let dir = tempdir().unwrap();
+        let store = SegmentStore::open(dir.path()).unwrap();
+        let long = "alpha ".repeat(200);
+        store.commit(vec![document("a", &long), document("b", "beta")]).unwrap();
+        store.commit(vec![StoredDocument::tombstone("b")]).unwrap();
+        store.commit(vec![document("c", "gamma")]).unwrap();
+        store.commit(vec![document("d", &"delta ".repeat(200))]).unwrap();
+
+        assert!(store.merge(2).unwrap());
+        assert_eq!(store.segment_count().unwrap(), 3);
+        let merged = store.readers().unwrap()[1].documents().unwrap();
+        let keys: Vec<(&str, bool)> = merged.iter().map(|d| (d.key.as_str(), d.deleted)).collect();
+        assert_eq!(keys, vec![("b", true), ("c", false)]);
+        let live: Vec<String> = store.documents().unwrap().into_iter().map(|d| d.key).collect();
+        assert_eq!(live, vec!["a", "c", "d"]);
+
+        assert!(store.merge(usize::MAX).unwrap());
+        assert!(store.readers().unwrap()[0].documents().unwrap().iter().all(|d| !d.deleted));
+        assert!(!store.merge(2).unwrap());
+    }
+
+    #[test]
+    fn test_merger_reports_failures_and_stops_retrying() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("index");
+        let store = Arc::new(SegmentStore::open(&path).unwrap());
+        for key in ["a", "b", "c"] {
+            store.commit(vec![document(key, "text")]).unwrap();
+        }
+        fs::remove_dir_all(&path).unwrap();
+
+        let merger = SegmentMerger::spawn_with_interval(store.clone(), 1, Duration::from_millis(5));
+        let wait = |done: &dyn Fn(&MergeStatus) -> bool| {
+            for _ in 0..400 {
+                if done(&merger.status()) {
+                    break;
+                }
+                thread::sleep(Duration::from_millis(5));
+            }
+        };
+        wait(&|status| status.failures >= u64::from(MAX_MERGE_ATTEMPTS));
+        thread::sleep(Duration::from_millis(50));
+        let status = merger.status();
+        assert_eq!(status.failures, u64::from(MAX_MERGE_ATTEMPTS));
+        assert_eq!(status.consecutive_failures, MAX_MERGE_ATTEMPTS);
+        assert!(status.last_error.is_some());
+        assert_eq!(store.segment_count().unwrap(), 3);
+
+        fs::create_dir_all(&path).unwrap();
+        merger.notify();
+        wait(&|status| status.merges > 0);
+        let status = merger.status();
+        assert_eq!((status.merges, status.consecutive_failures), (1, 0));
+        assert_eq!(store.segment_count().unwrap(), 1);
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub text: String,
+    pub score: f32,
+}
+
+#[derive(Default)]
+struct TrieNode {
+    children: BTreeMap<char, TrieNode>,
+    entries: BTreeMap<String, u32>,
+}
+
+impl TrieNode {
+    fn is_empty(&self) -> bool {
+        self.children.is_empty() && self.entries.is_empty()
+    }
+
+    fn remove(&mut self, path: &[char], text: &str) -> bool {
+        let (first, rest) = match path.split_first() {
+            Some(split) => split,
+            None => {
+                return match self.entries.get_mut(text) {
+                    Some(count) if *count > 1 => {
+                        *count -= 1;
+                        true
+                    }
+                    Some(_) => {
+                        self.entries.remove(text);
+                        true
+                    }
+                    None => false,
+                };
+            }
+        };
+
+        let child = match self.children.get_mut(first) {
+            Some(child) => child,
+            None => return false,
+        };
+        let removed = child.remove(rest, text);
+        if child.is_empty() {
+            self.children.remove(first);
+        }
+        removed
+    }
+
+    fn collect<'a>(&'a self, out: &mut Vec<(&'a str, u32)>) {
+        out.extend(self.entries.iter().map(|(text, count)| (text.as_str(), *count)));
+        for child in self.children.values() {
+            child.collect(out);
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SuggestionTrie {
+    root: TrieNode,
+}
+
+impl SuggestionTrie {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, text: &str) {
+        let mut node = &mut self.root;
+        for c in text.to_lowercase().chars() {
+            node = node.children.entry(c).or_default();
+        }
+        *node.entries.entry(text.to_string()).or_insert(0) += 1;
+    }
+
+    pub fn remove(&mut self, text: &str) -> bool {
+        let path: Vec<char> = text.to_lowercase().chars().collect();
+        self.root.remove(&path, text)
+    }
+
+    pub fn complete(&self, prefix: &str) -> Vec<(&str, u32)> {
+        let mut node = &self.root;
+        for c in prefix.to_lowercase().chars() {
+            node = match node.children.get(&c) {
+                Some(child) => child,
+                None => return Vec::new(),
+            };
+        }
+
+        let mut completions = Vec::new();
+        node.collect(&mut completions);
+        completions
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::SearchIndex;
+
+    #[test]
+    fn test_trie_counts_and_pruning() {
+        let mut trie = SuggestionTrie::new();
+        trie.insert("Report.txt");
+        trie.insert("report");
+        trie.insert("report");
+        trie.insert("repo");
+
+        assert_eq!(trie.complete("REP"), vec![("repo", 1), ("report", 2), ("Report.txt", 1)]);
+        assert!(trie.complete("x").is_empty());
+
+        assert!(trie.remove("report"));
+        assert_eq!(trie.complete("report"), vec![("report", 1), ("Report.txt", 1)]);
+        for text in ["report", "Report.txt", "repo"] {
+            assert!(trie.remove(text));
+        }
+        assert!(!trie.remove("repo"));
+        assert!(trie.is_empty());
+    }
+
+    #[test]
+    fn test_autocomplete_prefers_popular_queries() {
+        let mut index = SearchIndex::new();
+        index.index_data("project-plan.md", b"project milestones and program budget", None);
+        index.index_data("progress.txt", b"progress report for the project", None);
+
+        let texts = |index: &SearchIndex| -> Vec<String> {
+            index.autocomplete("pro", 3).into_iter().map(|s| s.text).collect()
+        };
+        assert_eq!(texts(&index), vec!["project", "program", "progress"]);
+
+        for _ in 0..3 {
+            index.query("progress", 10).unwrap();
+        }
+        assert_eq!(texts(&index)[0], "progress");
+
+        index.remove("progress.txt");
+        assert!(!texts(&index).contains(&"progress".to_string()));
+        assert!(index.autocomplete("", 5).is_empty());
+    }
+}
@@ -0,0 +1,156 @@
+pub struct LevenshteinAutomaton {
+    query: Vec<char>,
+    max_distance: usize,
+}
+
+impl LevenshteinAutomaton {
+    pub fn new(query: &str, max_distance: usize) -> Self {
+        LevenshteinAutomaton {
+            query: query.chars().collect(),
+            max_distance,
+        }
+    }
+
+    fn start(&self) -> Vec<usize> {
+        (0..=self.query.len()).collect()
+    }
+
+    fn step(&self, rows: &[Vec<usize>], chars: &[char], c: char) -> Vec<usize> {
+        let prev = &rows[rows.len() - 1];
+        let depth = rows.len();
+        let mut row = Vec::with_capacity(prev.len());
+        row.push(prev[0] + 1);
+
+        for j in 1..=self.query.len() {
+            let cost = usize::from(self.query[j - 1] != c);
+            let mut distance = (prev[j] + 1).min(row[j - 1] + 1).min(prev[j - 1] + cost);
+
+            if depth >= 2 && j >= 2 && self.query[j - 1] == chars[depth - 2] && self.query[j - 2] == c {
+                distance = distance.min(rows[depth - 2][j - 2] + 1);
+            }
+            row.push(distance);
+        }
+        row
+    }
+
+    fn can_match(&self, row: &[usize]) -> bool {
+        row.iter().min().is_some_and(|min| *min <= self.max_distance)
+    }
+
+    fn distance(&self, row: &[usize]) -> Option<usize> {
+        let distance = row[self.query.len()];
+        if distance <= self.max_distance {
+            Some(distance)
+        } else {
+            None
+        }
+    }
+
+    pub fn matches(&self, term: &str) -> Option<usize> {
+        let mut rows = vec![self.start()];
+        let mut chars = Vec::new();
+        for c in term.chars() {
+            let row = self.step(&rows, &chars, c);
+            if !self.can_match(&row) {
+                return None;
+            }
+            rows.push(row);
+            chars.push(c);
+        }
+        self.distance(&rows[rows.len() - 1])
+    }
+
+    pub fn search_sorted<'a, I>(&self, terms: I) -> Vec<(&'a str, usize)>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut matches = Vec::new();
+        let mut rows = vec![self.start()];
+        let mut chars: Vec<char> = Vec::new();
+
+        for term in terms {
+            let term_chars: Vec<char> = term.chars().collect();
+            let common = chars
+                .iter()
+                .zip(&term_chars)
+                .take_while(|(a, b)| a == b)
+                .count();
+            rows.truncate(common + 1);
+            chars.truncate(common);
+
+            let mut alive = self.can_match(&rows[rows.len() - 1]);
+            for &c in &term_chars[common..] {
+                if !alive {
+                    break;
+                }
+                let row = self.step(&rows, &chars, c);
+                alive = self.can_match(&row);
+                rows.push(row);
+                chars.push(c);
+            }
+
+            if alive && chars.len() == term_chars.len() {
+                if let Some(distance) = self.distance(&rows[rows.len() - 1]) {
+                    matches.push((term, distance));
+                }
+            }
+        }
+
+        matches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, MatcherKind};
+    use crate::search::SearchIndex;
+
+    #[test]
+    fn test_damerau_distance() {
+        let automaton = LevenshteinAutomaton::new("receive", 2);
+        assert_eq!(automaton.matches("receive"), Some(0));
+        assert_eq!(automaton.matches("recieve"), Some(1));
+        assert_eq!(automaton.matches("recive"), Some(1));
+        assert_eq!(automaton.matches("deceit"), None);
+
+        let automaton = LevenshteinAutomaton::new("the", 1);
+        assert_eq!(automaton.matches("teh"), Some(1));
+        assert_eq!(automaton.matches("thy"), Some(1));
+        assert_eq!(automaton.matches("tea"), None);
+        assert_eq!(automaton.matches("eth"), None);
+    }
+
+    #[test]
+    fn test_search_sorted_dictionary() {
+        let mut terms = ["receipt", "receive", "received", "recieve", "recipe", "relieve", "zebra"];
+        terms.sort();
+
+        let automaton = LevenshteinAutomaton::new("recieve", 1);
+        let matches = automaton.search_sorted(terms.iter().copied());
+        assert_eq!(matches, vec![("receive", 1), ("recieve", 0), ("relieve", 1)]);
+
+        let automaton = LevenshteinAutomaton::new("recieve", 2);
+        let found: Vec<&str> = automaton
+            .search_sorted(terms.iter().copied())
+            .into_iter()
+            .map(|(term, _)| term)
+            .collect();
+        assert_eq!(found, vec!["receive", "received", "recieve", "recipe", "relieve"]);
+    }
+
+    #[test]
+    fn test_typo_matcher_in_index() {
+        let mut config = Config::default().search;
+        config.matcher = MatcherKind::Typo;
+        let mut index = SearchIndex::with_config(config);
+        index.index_data("invoice-march.txt", b"payment received", None);
+        index.index_data("notes.txt", b"meeting agenda", None);
+
+        let keys: Vec<String> = index.query("recieved", 10).unwrap().into_iter().map(|r| r.key).collect();
+        assert_eq!(keys, vec!["invoice-march.txt"]);
+
+        let keys: Vec<String> = index.query("invoise", 10).unwrap().into_iter().map(|r| r.key).collect();
+        assert_eq!(keys, vec!["invoice-march.txt"]);
+    }
+}
@@ -0,0 +1,441 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use super::algorithms::cosine_similarity;
+
+const DEFAULT_M: usize = 16;
+const DEFAULT_EF_CONSTRUCTION: usize = 200;
+const DEFAULT_EF_SEARCH: usize = 64;
+const EXACT_SEARCH_THRESHOLD: usize = 1000;
+
+pub struct VectorIndex {
+    dimensions: usize,
+    vectors: HashMap<String, Vec<f32>>,
+    graph: Hnsw,
+    ef_search: usize,
+    exact_threshold: usize,
+}
+
+impl VectorIndex {
+    pub fn new(dimensions: usize) -> Self {
+        Self::with_params(dimensions, DEFAULT_M, DEFAULT_EF_CONSTRUCTION, DEFAULT_EF_SEARCH)
+    }
+
+    pub fn with_params(dimensions: usize, m: usize, ef_construction: usize, ef_search: usize) -> Self {
+        VectorIndex {
+            dimensions,
+            vectors: HashMap::new(),
+            graph: Hnsw::new(m.max(2), ef_construction.max(1)),
+            ef_search: ef_search.max(1),
+            exact_threshold: EXACT_SEARCH_THRESHOLD,
+        }
+    }
+
+    pub fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    pub fn get(&self, key: &str) -> Option<&[f32]> {
+        self.vectors.get(key).map(|vector| vector.as_slice())
+    }
+
+    pub fn set_ef_search(&mut self, ef_search: usize) {
+        self.ef_search = ef_search.max(1);
+    }
+
+    pub fn set_exact_threshold(&mut self, threshold: usize) {
+        self.exact_threshold = threshold;
+    }
+
+    pub fn insert(&mut self, key: &str, vector: &[f32]) -> Result<(), String> {
+        if vector.len() != self.dimensions {
+            return Err(format!(
+                "Vector has {} dimensions, index expects {}",
+                vector.len(),
+                self.dimensions
+            ));
+        }
+        if !vector.iter().all(|component| component.is_finite()) {
+            return Err("Vector components must be finite".to_string());
+        }
+
+        self.graph.remove(key);
+        self.graph.insert(key, vector);
+        self.vectors.insert(key.to_string(), vector.to_vec());
+
+        if self.graph.deleted_count() > self.vectors.len() {
+            self.rebuild();
+        }
+        Ok(())
+    }
+
+    pub fn remove(&mut self, key: &str) -> bool {
+        if self.vectors.remove(key).is_none() {
+            return false;
+        }
+        self.graph.remove(key);
+
+        if self.graph.deleted_count() > self.vectors.len() {
+            self.rebuild();
+        }
+        true
+    }
+
+    pub fn search(&self, query: &[f32], k: usize) -> Vec<(String, f32)> {
+        if query.len() != self.dimensions || k == 0 || !query.iter().all(|component| component.is_finite()) {
+            return Vec::new();
+        }
+
+        if self.vectors.len() <= self.exact_threshold {
+            return self.exact_search(query, k);
+        }
+
+        let results = self.graph.search(query, k, self.ef_search.max(k));
+        if results.len() < k.min(self.vectors.len()) {
+            return self.exact_search(query, k);
+        }
+        results
+            .into_iter()
+            .map(|(key, distance)| (key, 1.0 - distance))
+            .collect()
+    }
+
+    pub fn exact_search(&self, query: &[f32], k: usize) -> Vec<(String, f32)> {
+        let mut results: Vec<(String, f32)> = self
+            .vectors
+            .iter()
+            .map(|(key, vector)| (key.clone(), cosine_similarity(query, vector)))
+            .collect();
+
+        results.sort_by(|a, b| b.1.total_cmp(&a.1));
+        results.truncate(k);
+        results
+    }
+
+    fn rebuild(&mut self) {
+        let mut graph = Hnsw::new(self.graph.m, self.graph.ef_construction);
+        for (key, vector) in &self.vectors {
+            graph.insert(key, vector);
+        }
+        self.graph = graph;
+    }
+}
+
+struct HnswNode {
+    key: String,
+    vector: Vec<f32>,
+    neighbors: Vec<Vec<usize>>,
+    deleted: bool,
+}
+
+struct Hnsw {
+    nodes: Vec<HnswNode>,
+    ids: HashMap<String, usize>,
+    entry_point: Option<usize>,
+    m: usize,
+    ef_construction: usize,
+    level_mult: f64,
+    rng_state: u64,
+    deleted: usize,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct Candidate {
+    distance: f32,
+    id: usize,
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance
+            .total_cmp(&other.distance)
+            .then_with(|| self.id.cmp(&other.id))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Hnsw {
+    fn new(m: usize, ef_construction: usize) -> Self {
+        Hnsw {
+            nodes: Vec::new(),
+            ids: HashMap::new(),
+            entry_point: None,
+            m,
+            ef_construction,
+            level_mult: 1.0 / (m as f64).ln(),
+            rng_state: 0x9E37_79B9_7F4A_7C15,
+            deleted: 0,
+        }
+    }
+
+    fn deleted_count(&self) -> usize {
+        self.deleted
+    }
+
+    fn distance(a: &[f32], b: &[f32]) -> f32 {
+        1.0 - cosine_similarity(a, b)
+    }
+
+    fn random_level(&mut self) -> usize {
+        self.rng_state ^= self.rng_state << 13;
+        self.rng_state ^= self.rng_state >> 7;
+        self.rng_state ^= self.rng_state << 17;
+        let uniform = ((self.rng_state >> 11) as f64 + 1.0) / (1u64 << 53) as f64;
+        (-uniform.ln() * self.level_mult).floor() as usize
+    }
+
+    fn max_neighbors(&self, layer: usize) -> usize {
+        if layer == 0 {
+            self.m * 2
+        } else {
+            self.m
+        }
+    }
+
+    fn insert(&mut self, key: &str, vector: &[f32]) {
+        let level = self.random_level();
+        let id = self.nodes.len();
+        self.nodes.push(HnswNode {
+            key: key.to_string(),
+            vector: vector.to_vec(),
+            neighbors: vec![Vec::new(); level + 1],
+            deleted: false,
+        });
+        self.ids.insert(key.to_string(), id);
+
+        let entry = match self.entry_point {
+            Some(entry) => entry,
+            None => {
+                self.entry_point = Some(id);
+                return;
+            }
+        };
+
+        let top_level = self.nodes[entry].neighbors.len() - 1;
+        let mut current = entry;
+        for layer in (level + 1..=top_level).rev() {
+            current = self.greedy_closest(vector, current, layer);
+        }
+
+        let mut entries = vec![current];
+        for layer in (0..=level.min(top_level)).rev() {
+            let candidates = self.search_layer(vector, &entries, self.ef_construction, layer);
+            let selected: Vec<usize> = candidates
+                .iter()
+                .take(self.max_neighbors(layer))
+                .map(|c| c.id)
+                .collect();
+
+            for &neighbor in &selected {
+                self.nodes[neighbor].neighbors[layer].push(id);
+                self.prune(neighbor, layer);
+            }
+            self.nodes[id].neighbors[layer] = selected;
+            entries = candidates.iter().map(|c| c.id).collect();
+        }
+
+        if level > top_level {
+            self.entry_point = Some(id);
+        }
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(id) = self.ids.remove(key) {
+            self.nodes[id].deleted = true;
+            self.deleted += 1;
+        }
+    }
+
+    fn prune(&mut self, id: usize, layer: usize) {
+        let limit = self.max_neighbors(layer);
+        if self.nodes[id].neighbors[layer].len() <= limit {
+            return;
+        }
+
+        let base = &self.nodes[id].vector;
+        let mut scored: Vec<Candidate> = self.nodes[id].neighbors[layer]
+            .iter()
+            .map(|&n| Candidate {
+                distance: Self::distance(base, &self.nodes[n].vector),
+                id: n,
+            })
+            .collect();
+        scored.sort();
+        scored.truncate(limit);
+        self.nodes[id].neighbors[layer] = scored.into_iter().map(|c| c.id).collect();
+    }
+
+    fn greedy_closest(&self, query: &[f32], start: usize, layer: usize) -> usize {
+        let mut current = start;
+        let mut best = Self::distance(query, &self.nodes[current].vector);
+        loop {
+            let mut changed = false;
+            for &neighbor in &self.nodes[current].neighbors[layer] {
+                let distance = Self::distance(query, &self.nodes[neighbor].vector);
+                if distance < best {
+                    best = distance;
+                    current = neighbor;
+                    changed = true;
+                }
+            }
+            if !changed {
+                return current;
+            }
+        }
+    }
+
+    fn search_layer(&self, query: &[f32], entries: &[usize], ef: usize, layer: usize) -> Vec<Candidate> {
+        let mut visited: HashSet<usize> = entries.iter().copied().collect();
+        let mut candidates = BinaryHeap::new();
+        let mut nearest = BinaryHeap::new();
+
+        for &entry in entries {
+            let candidate = Candidate {
+                distance: Self::distance(query, &self.nodes[entry].vector),
+                id: entry,
+            };
+            candidates.push(std::cmp::Reverse(candidate));
+            nearest.push(candidate);
+        }
+        while nearest.len() > ef {
+            nearest.pop();
+        }
+
+        while let Some(std::cmp::Reverse(closest)) = candidates.pop() {
+            if let Some(furthest) = nearest.peek() {
+                if closest.distance > furthest.distance && nearest.len() >= ef {
+                    break;
+                }
+            }
+
+            for &neighbor in &self.nodes[closest.id].neighbors[layer] {
+                if !visited.insert(neighbor) {
+                    continue;
+                }
+
+                let candidate = Candidate {
+                    distance: Self::distance(query, &self.nodes[neighbor].vector),
+                    id: neighbor,
+                };
+                let admit = nearest.len() < ef
+                    || nearest.peek().is_none_or(|f| candidate.distance < f.distance);
+                if admit {
+                    candidates.push(std::cmp::Reverse(candidate));
+                    nearest.push(candidate);
+                    if nearest.len() > ef {
+                        nearest.pop();
+                    }
+                }
+            }
+        }
+
+        nearest.into_sorted_vec()
+    }
+
+    fn search(&self, query: &[f32], k: usize, ef: usize) -> Vec<(String, f32)> {
+        let entry = match self.entry_point {
+            Some(entry) => entry,
+            None => return Vec::new(),
+        };
+
+        let mut current = entry;
+        for layer in (1..self.nodes[entry].neighbors.len()).rev() {
+            current = self.greedy_closest(query, current, layer);
+        }
+
+        let wanted = k.min(self.nodes.len() - self.deleted);
+        let mut ef = ef.max(k);
+        loop {
+            let results: Vec<(String, f32)> = self
+                .search_layer(query, &[current], ef, 0)
+                .into_iter()
+                .filter(|c| !self.nodes[c.id].deleted)
+                .take(k)
+                .map(|c| (self.nodes[c.id].key.clone(), c.distance))
+                .collect();
+            if results.len() >= wanted || ef >= self.nodes.len() {
+                return results;
+            }
+            ef = (ef * 2).min(self.nodes.len());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_vector(seed: u64, dimensions: usize) -> Vec<f32> {
+        let mut state = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (0..dimensions)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                ((state >> 40) as f32 / (1u64 << 24) as f32) - 0.5
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_dimension_enforcement() {
+        let mut index = VectorIndex::new(3);
+        assert!(index.insert("a", &[1.0, 0.0, 0.0]).is_ok());
+        assert!(index.insert("b", &[1.0, 0.0]).is_err());
+        assert!(index.insert("nan", &[f32::NAN, 0.0, 0.0]).is_err());
+        assert!(index.insert("inf", &[1.0, f32::INFINITY, 0.0]).is_err());
+        assert!(index.search(&[1.0, 0.0], 1).is_empty());
+        assert!(index.search(&[f32::NAN, 0.0, 0.0], 1).is_empty());
+        assert_eq!(index.search(&[1.0, 0.0, 0.0], 2).len(), 1);
+        assert!(index.remove("a"));
+        assert!(!index.remove("a"));
+    }
+
+    #[test]
+    fn test_hnsw_matches_exact_search() {
+        let mut index = VectorIndex::with_params(16, 8, 64, 64);
+        index.set_exact_threshold(0);
+        for i in 0..500 {
+            index.insert(&format!("doc{}", i), &sample_vector(i, 16)).unwrap();
+        }
+
+        let query = sample_vector(42, 16);
+        let approx = index.search(&query, 10);
+        let exact = index.exact_search(&query, 10);
+        assert_eq!(approx[0].0, exact[0].0);
+
+        let overlap = approx
+            .iter()
+            .filter(|(key, _)| exact.iter().any(|(k, _)| k == key))
+            .count();
+        assert!(overlap >= 8);
+
+        index.remove("doc42");
+        assert!(index.search(&query, 10).iter().all(|(key, _)| key != "doc42"));
+    }
+
+    #[test]
+    fn test_search_skips_past_deleted_neighbors() {
+        let mut index = VectorIndex::with_params(16, 8, 64, 4);
+        index.set_exact_threshold(0);
+        for i in 0..600 {
+            index.insert(&format!("doc{}", i), &sample_vector(i, 16)).unwrap();
+        }
+
+        let query = sample_vector(7, 16);
+        for (key, _) in index.exact_search(&query, 250) {
+            index.remove(&key);
+        }
+
+        let approx = index.search(&query, 10);
+        let exact = index.exact_search(&query, 10);
+        assert_eq!(approx.len(), 10);
+        assert_eq!(approx[0].0, exact[0].0);
+        assert!(approx.iter().all(|(key, _)| index.get(key).is_some()));
+    }
+}
@@ -1,38 +1,374 @@
 using System;
+using System.Collections.Generic;
 using System.Runtime.InteropServices;
+using System.Text;
 
-public static class Fabric
+public class FabricSearchResult
 {
+    public string Key { get; set; }
+    public float Score { get; set; }
+    public Dictionary<string, string> Metadata { get; set; }
+    public int[] Positions { get; set; }
+}
+
+public enum FabricError
+{
+    None = 0,
+    InvalidArgument = 1,
+    Failed = 2,
+    Overloaded = 3
+}
+
+public class FabricOverloadedException : Exception
+{
+    public FabricOverloadedException()
+        : base("Too many concurrent searches")
+    {
+    }
+}
+
+public sealed class Fabric : IDisposable
+{
+    [DllImport("fabric", CallingConvention = CallingConvention.Cdecl)]
+    private static extern IntPtr fabric_open([MarshalAs(UnmanagedType.LPUTF8Str)] string name, [MarshalAs(UnmanagedType.LPUTF8Str)] string configPath);
+
+    [DllImport("fabric", CallingConvention = CallingConvention.Cdecl)]
+    private static extern void fabric_close(IntPtr handle);
+
+    [DllImport("fabric", CallingConvention = CallingConvention.Cdecl)]
+    [return: MarshalAs(UnmanagedType.I1)]
+    private static extern bool fabric_index_data(IntPtr handle, [MarshalAs(UnmanagedType.LPUTF8Str)] string key, byte[] data, UIntPtr length);
+
+    [DllImport("fabric", CallingConvention = CallingConvention.Cdecl)]
+    [return: MarshalAs(UnmanagedType.I1)]
+    private static extern bool fabric_remove(IntPtr handle, [MarshalAs(UnmanagedType.LPUTF8Str)] string key);
+
+    [DllImport("fabric", CallingConvention = CallingConvention.Cdecl)]
+    [return: MarshalAs(UnmanagedType.I1)]
+    private static extern bool fabric_update(IntPtr handle, [MarshalAs(UnmanagedType.LPUTF8Str)] string key, byte[] data, UIntPtr length, [MarshalAs(UnmanagedType.LPUTF8Str)] string metadataJson);
+
+    [DllImport("fabric", CallingConvention = CallingConvention.Cdecl)]
+    [return: MarshalAs(UnmanagedType.I1)]
+    private static extern bool fabric_search(IntPtr handle, [MarshalAs(UnmanagedType.LPUTF8Str)] string query, out IntPtr result);
+
+    [DllImport("fabric", CallingConvention = CallingConvention.Cdecl)]
+    private static extern IntPtr fabric_search_ex(IntPtr handle, [MarshalAs(UnmanagedType.LPUTF8Str)] string query, UIntPtr limit, UIntPtr offset);
+
+    [DllImport("fabric", CallingConvention = CallingConvention.Cdecl)]
+    private static extern IntPtr fabric_autocomplete(IntPtr handle, [MarshalAs(UnmanagedType.LPUTF8Str)] string prefix, UIntPtr limit);
+
+    [DllImport("fabric", CallingConvention = CallingConvention.Cdecl)]
+    private static extern UIntPtr fabric_result_count(IntPtr results);
+
+    [DllImport("fabric", CallingConvention = CallingConvention.Cdecl)]
+    private static extern IntPtr fabric_result_get_key(IntPtr results, UIntPtr index);
+
+    [DllImport("fabric", CallingConvention = CallingConvention.Cdecl)]
+    private static extern float fabric_result_get_score(IntPtr results, UIntPtr index);
+
+    [DllImport("fabric", CallingConvention = CallingConvention.Cdecl)]
+    private static extern IntPtr fabric_result_get_metadata_json(IntPtr results, UIntPtr index);
+
     [DllImport("fabric", CallingConvention = CallingConvention.Cdecl)]
-    private static extern bool fabric_init();
+    private static extern IntPtr fabric_result_get_positions(IntPtr results, UIntPtr index, out UIntPtr count);
 
     [DllImport("fabric", CallingConvention = CallingConvention.Cdecl)]
-    private static extern bool fabric_index_data(string key, byte[] data, int length);
+    private static extern void fabric_result_free(IntPtr results);
 
     [DllImport("fabric", CallingConvention = CallingConvention.Cdecl)]
-    private static extern bool fabric_search(string query, out IntPtr result);
+    [return: MarshalAs(UnmanagedType.I1)]
+    private static extern bool fabric_flush(IntPtr handle);
+
+    [DllImport("fabric", CallingConvention = CallingConvention.Cdecl)]
+    private static extern FabricError fabric_last_error();
 
     [DllImport("fabric", CallingConvention = CallingConvention.Cdecl)]
     private static extern void fabric_free_string(IntPtr str);
 
-    public static bool Init()
+    private IntPtr handle;
+
+    private Fabric(IntPtr handle)
+    {
+        this.handle = handle;
+    }
+
+    ~Fabric()
+    {
+        Close();
+    }
+
+    public static Fabric Open(string name, string configPath = null)
+    {
+        IntPtr handle = fabric_open(name, configPath);
+        return handle != IntPtr.Zero ? new Fabric(handle) : null;
+    }
+
+    public void Dispose()
+    {
+        Close();
+        GC.SuppressFinalize(this);
+    }
+
+    private void Close()
+    {
+        if (handle != IntPtr.Zero)
+        {
+            fabric_close(handle);
+            handle = IntPtr.Zero;
+        }
+    }
+
+    private IntPtr Handle
     {
-        return fabric_init();
+        get
+        {
+            if (handle == IntPtr.Zero)
+            {
+                throw new ObjectDisposedException(nameof(Fabric));
+            }
+            return handle;
+        }
     }
 
-    public static bool IndexData(string key, byte[] data)
+    public bool IndexData(string key, byte[] data)
     {
-        return fabric_index_data(key, data, data.Length);
+        return fabric_index_data(Handle, key, data, (UIntPtr)data.Length);
     }
 
-    public static string Search(string query)
+    public bool Remove(string key)
     {
-        if (fabric_search(query, out IntPtr result))
+        return fabric_remove(Handle, key);
+    }
+
+    public bool Flush()
+    {
+        return fabric_flush(Handle);
+    }
+
+    public bool Update(string key, byte[] data, IDictionary<string, string> metadata = null)
+    {
+        string metadataJson = metadata != null ? ToJson(metadata) : null;
+        return fabric_update(Handle, key, data, (UIntPtr)(data != null ? data.Length : 0), metadataJson);
+    }
+
+    public string Search(string query)
+    {
+        if (fabric_search(Handle, query, out IntPtr result))
         {
-            string str = Marshal.PtrToStringAnsi(result);
+            string str = PtrToStringUtf8(result);
             fabric_free_string(result);
             return str;
         }
+        ThrowIfOverloaded();
         return null;
     }
+
+    public List<FabricSearchResult> SearchEx(string query, int limit = 10, int offset = 0)
+    {
+        var results = new List<FabricSearchResult>();
+        IntPtr set = fabric_search_ex(Handle, query, (UIntPtr)limit, (UIntPtr)offset);
+        if (set == IntPtr.Zero)
+        {
+            ThrowIfOverloaded();
+            return results;
+        }
+
+        try
+        {
+            ulong count = (ulong)fabric_result_count(set);
+            for (ulong i = 0; i < count; i++)
+            {
+                var index = (UIntPtr)i;
+                results.Add(new FabricSearchResult
+                {
+                    Key = PtrToStringUtf8(fabric_result_get_key(set, index)),
+                    Score = fabric_result_get_score(set, index),
+                    Metadata = FromJson(PtrToStringUtf8(fabric_result_get_metadata_json(set, index))),
+                    Positions = ReadPositions(set, index)
+                });
+            }
+        }
+        finally
+        {
+            fabric_result_free(set);
+        }
+        return results;
+    }
+
+    public List<string> Autocomplete(string prefix, int limit = 10)
+    {
+        var suggestions = new List<string>();
+        IntPtr set = fabric_autocomplete(Handle, prefix, (UIntPtr)limit);
+        if (set == IntPtr.Zero)
+        {
+            ThrowIfOverloaded();
+            return suggestions;
+        }
+
+        try
+        {
+            ulong count = (ulong)fabric_result_count(set);
+            for (ulong i = 0; i < count; i++)
+            {
+                suggestions.Add(PtrToStringUtf8(fabric_result_get_key(set, (UIntPtr)i)));
+            }
+        }
+        finally
+        {
+            fabric_result_free(set);
+        }
+        return suggestions;
+    }
+
+    private static void ThrowIfOverloaded()
+    {
+        if (fabric_last_error() == FabricError.Overloaded)
+        {
+            throw new FabricOverloadedException();
+        }
+    }
+
+    private static int[] ReadPositions(IntPtr set, UIntPtr index)
+    {
+        IntPtr ptr = fabric_result_get_positions(set, index, out UIntPtr count);
+        var positions = new int[(int)(ulong)count];
+        for (int i = 0; i < positions.Length; i++)
+        {
+            positions[i] = (int)Marshal.ReadIntPtr(ptr, i * IntPtr.Size).ToInt64();
+        }
+        return positions;
+    }
+
+    private static string PtrToStringUtf8(IntPtr ptr)
+    {
+        if (ptr == IntPtr.Zero)
+        {
+            return null;
+        }
+
+        int length = 0;
+        while (Marshal.ReadByte(ptr, length) != 0)
+        {
+            length++;
+        }
+        byte[] buffer = new byte[length];
+        Marshal.Copy(ptr, buffer, 0, length);
+        return Encoding.UTF8.GetString(buffer);
+    }
+
+    private static Dictionary<string, string> FromJson(string json)
+    {
+        var metadata = new Dictionary<string, string>();
+        if (string.IsNullOrEmpty(json))
+        {
+            return metadata;
+        }
+
+        int pos = 0;
+        SkipWhitespace(json, ref pos);
+        if (pos >= json.Length || json[pos] != '{')
+        {
+            return metadata;
+        }
+        pos++;
+
+        while (true)
+        {
+            SkipWhitespace(json, ref pos);
+            if (pos >= json.Length || json[pos] == '}')
+            {
+                break;
+            }
+
+            string key = ReadJsonString(json, ref pos);
+            SkipWhitespace(json, ref pos);
+            pos++;
+            SkipWhitespace(json, ref pos);
+            string value = ReadJsonString(json, ref pos);
+            metadata[key] = value;
+
+            SkipWhitespace(json, ref pos);
+            if (pos < json.Length && json[pos] == ',')
+            {
+                pos++;
+            }
+        }
+        return metadata;
+    }
+
+    private static void SkipWhitespace(string json, ref int pos)
+    {
+        while (pos < json.Length && char.IsWhiteSpace(json[pos]))
+        {
+            pos++;
+        }
+    }
+
+    private static string ReadJsonString(string json, ref int pos)
+    {
+        var sb = new StringBuilder();
+        pos++;
+        while (pos < json.Length && json[pos] != '"')
+        {
+            char c = json[pos++];
+            if (c != '\\' || pos >= json.Length)
+            {
+                sb.Append(c);
+                continue;
+            }
+
+            char escaped = json[pos++];
+            switch (escaped)
+            {
+                case 'n': sb.Append('\n'); break;
+                case 'r': sb.Append('\r'); break;
+                case 't': sb.Append('\t'); break;
+                case 'b': sb.Append('\b'); break;
+                case 'f': sb.Append('\f'); break;
+                case 'u':
+                    sb.Append((char)Convert.ToInt32(json.Substring(pos, 4), 16));
+                    pos += 4;
+                    break;
+                default: sb.Append(escaped); break;
+            }
+        }
+        pos++;
+        return sb.ToString();
+    }
+
+    private static string ToJson(IDictionary<string, string> metadata)
+    {
+        var sb = new StringBuilder("{");
+        bool first = true;
+        foreach (var pair in metadata)
+        {
+            if (!first) sb.Append(',');
+            first = false;
+            AppendJsonString(sb, pair.Key);
+            sb.Append(':');
+            AppendJsonString(sb, pair.Value);
+        }
+        return sb.Append('}').ToString();
+    }
+
+    private static void AppendJsonString(StringBuilder sb, string value)
+    {
+        sb.Append('"');
+        foreach (char c in value)
+        {
+            switch (c)
+            {
+                case '"': sb.Append("\\\""); break;
+                case '\\': sb.Append("\\\\"); break;
+                case '\n': sb.Append("\\n"); break;
+                case '\r': sb.Append("\\r"); break;
+                case '\t': sb.Append("\\t"); break;
+                default:
+                    if (c < 0x20) sb.AppendFormat("\\u{0:x4}", (int)c);
+                    else sb.Append(c);
+                    break;
+            }
+        }
+        sb.Append('"');
+    }
 }